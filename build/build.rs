@@ -10,7 +10,7 @@ use image::GenericImage;
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
 
-    let img = image::open(&Path::new(&"build/consolas-18px-ascii-table.png")).unwrap();
+    let img = image::open(Path::new(&"build/consolas-18px-ascii-table.png")).unwrap();
 
     // Character sizes are 7 wide, 12 tall
 
@@ -19,7 +19,7 @@ fn main() {
 
     // Printables are 0x20 - 0x7E
 
-    let mut output = "pub const ASCII_TABLE: [[u8; 170]; 127] = [\n".to_string();
+    let mut output = "pub static ASCII_TABLE: [[u8; 170]; 127] = [\n".to_string();
 
     // 0x00 to 0x20 is filled with blank
     for _ in 0..(32 + 1) {
@@ -47,6 +47,6 @@ fn main() {
 
     output += "];";
 
-    let mut f = File::create(&Path::new(&out_dir).join("fonts.rs")).unwrap();
+    let mut f = File::create(Path::new(&out_dir).join("fonts.rs")).unwrap();
     let _ = f.write_all(output.as_bytes());
 }
\ No newline at end of file
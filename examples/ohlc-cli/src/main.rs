@@ -4,14 +4,14 @@ extern crate serde_json;
 
 use argh::FromArgs;
 use ohlc::*;
-use ohlc::model::data::OHLC;
+use ohlc::model::data::OHLCV;
 use ohlc::model::rex::{BollingerBands, DEMA, EMA, MACD, RSI};
 use std::fs;
 
 #[derive(FromArgs)]
 /// frontend for generating charts using ohlc-rs
 struct CliOptions {
-    /// source data file to feed into the chart generator
+    /// source data file to feed into the chart generator, a JSON array of candles or a CSV export
     #[argh(positional)]
     input: String,
     /// where the image is (over)written to
@@ -42,11 +42,11 @@ fn default_output() -> String {
 fn main() {
     let options: CliOptions = argh::from_env();
 
-    let mut ohlc: OHLCRenderOptions<OHLC> = OHLCRenderOptions::new();
+    let mut ohlc: OHLCRenderOptions<OHLCV> = OHLCRenderOptions::new();
 
     ohlc.title("ohlc-rs demo", 0xFFFFFFFF);
     ohlc.background_colour(0x444444FF);
-    ohlc.line(0xEEEEEEFF, 500.0, 24);
+    ohlc.auto_line(0xEEEEEEFF, 50, 150);
 
     if options.bb {
         ohlc.add_extension(BollingerBands::new(20, 2, 0x00AAAAFF));
@@ -68,7 +68,11 @@ fn main() {
         .map_err(|err| format!("{:?}", err))).unwrap().unwrap();
 }
 
-fn get_data(path: &str) -> Vec<OHLC> {
+fn get_data(path: &str) -> Vec<OHLCV> {
+    if path.to_lowercase().ends_with(".csv") {
+        return CsvLoader::new().load_path(path).unwrap();
+    }
+
     return serde_json::from_reader(fs::OpenOptions::new()
         .read(true)
         .open(path)
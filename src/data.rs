@@ -8,4 +8,12 @@ pub trait Candle {
 		self.buy_volume().map(|buy| self.total_volume() - buy)
 	}
 	fn total_volume(&self) -> f64;
+	/// Unix timestamp, in seconds, of when the candle opens
+	fn timestamp(&self) -> Option<i64> {
+		None
+	}
+	/// Copy of the candle with other prices, used to repair invalid candles. Candles that can't be copied aren't repaired.
+	fn with_prices(&self, _open: f64, _high: f64, _low: f64, _close: f64) -> Option<Self> where Self: Sized {
+		None
+	}
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use validation::ViolationKind;
+
+/// Value of a candle that failed validation
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CandleField {
+	Open,
+	High,
+	Low,
+	Close,
+	Volume,
+	BuyVolume,
+	Timestamp,
+}
+
+/// Everything that can go wrong while rendering a chart
+#[derive(Debug)]
+pub enum OhlcError {
+	/// A candle doesn't make sense, e.g. it opened above its high
+	Validation { index: usize, field: CandleField, kind: ViolationKind },
+	/// There are no candles where at least one is needed
+	EmptyData,
+	/// The chart doesn't fit into the image, e.g. the margins are bigger than the image
+	Layout(String),
+	/// A font couldn't be loaded, e.g. its data isn't a TrueType/OpenType font
+	Font(String),
+	/// The image couldn't be encoded, e.g. the file extension has no encoder
+	Encoding(String),
+	/// A row of the data couldn't be read, lines count from 1
+	Parse { line: usize, reason: String },
+	/// Reading or writing files failed
+	Io(io::Error),
+}
+
+impl OhlcError {
+	/// Sorts the errors of the image encoders, which report unsupported formats as invalid input
+	pub(crate) fn from_encoder(err: io::Error) -> OhlcError {
+		match err.kind() {
+			io::ErrorKind::InvalidInput => OhlcError::Encoding(err.to_string()),
+			_ => OhlcError::Io(err),
+		}
+	}
+}
+
+impl fmt::Display for OhlcError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			OhlcError::Validation { index, field, kind } => write!(f, "Candle {} has an invalid {:?} value: {}", index, field, kind.description()),
+			OhlcError::EmptyData => write!(f, "There are no candles."),
+			OhlcError::Layout(ref reason) => write!(f, "Layout error: {}", reason),
+			OhlcError::Font(ref reason) => write!(f, "Font error: {}", reason),
+			OhlcError::Encoding(ref reason) => write!(f, "Image encoding error: {}", reason),
+			OhlcError::Parse { line, ref reason } => write!(f, "Parse error on line {}: {}", line, reason),
+			OhlcError::Io(ref err) => write!(f, "I/O error: {}", err),
+		}
+	}
+}
+
+impl Error for OhlcError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match *self {
+			OhlcError::Io(ref err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for OhlcError {
+	fn from(err: io::Error) -> OhlcError {
+		OhlcError::Io(err)
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn encoder_error_test() {
+	let unsupported = io::Error::new(io::ErrorKind::InvalidInput, "Unsupported image format image/\"gif\"");
+	let denied = io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied");
+
+	match OhlcError::from_encoder(unsupported) {
+		OhlcError::Encoding(_) => {}
+		err => panic!("expected an encoding error, got {:?}", err),
+	}
+
+	match OhlcError::from_encoder(denied) {
+		err @ OhlcError::Io(_) => assert!(err.source().is_some()),
+		err => panic!("expected an I/O error, got {:?}", err),
+	}
+}
@@ -0,0 +1,256 @@
+/// Amount of decimal places of formatted numbers
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Precision {
+	/// Always this many decimal places
+	Fixed(usize),
+	/// Enough decimal places to show prices of an instrument quoted in steps of this tick size, e.g. 2 for 0.01
+	Tick(f64),
+	/// Just enough decimal places to tell apart numbers a tick apart, or 5 significant digits (trailing zeros trimmed) without a tick
+	Auto,
+}
+
+/// Formatting of prices and volumes in labels, mutate through the methods
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NumberFormat {
+	pub precision: Precision,
+	/// Separator between groups of thousands, e.g. ','
+	pub thousands_separator: Option<char>,
+	/// Abbreviate thousands, millions, billions and trillions with K, M, B and T
+	pub si_suffixes: bool,
+	/// Put in front of the number, e.g. "$"
+	pub prefix: String,
+	/// Put behind the number, e.g. " USD"
+	pub suffix: String,
+}
+
+const SI_UNITS: [(f64, &str); 4] = [(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "K")];
+
+/// Upper bound of decimal places, beyond which f64 stops being meaningful for prices anyway
+const MAX_DECIMALS: usize = 12;
+
+impl Default for NumberFormat {
+	fn default() -> NumberFormat {
+		NumberFormat::new()
+	}
+}
+
+impl NumberFormat {
+	/// Creates a format with automatic precision and no decorations
+	pub fn new() -> NumberFormat {
+		NumberFormat {
+			precision: Precision::Auto,
+			thousands_separator: None,
+			si_suffixes: false,
+			prefix: String::new(),
+			suffix: String::new(),
+		}
+	}
+
+	pub fn decimals(mut self, decimals: usize) -> NumberFormat {
+		self.precision = Precision::Fixed(decimals);
+
+		self
+	}
+
+	/// Formats numbers with the decimal places of the instrument's tick size, e.g. `.tick_size(0.01)` shows 48213.57
+	pub fn tick_size(mut self, tick_size: f64) -> NumberFormat {
+		self.precision = Precision::Tick(tick_size);
+
+		self
+	}
+
+	pub fn thousands_separator(mut self, separator: char) -> NumberFormat {
+		self.thousands_separator = Some(separator);
+
+		self
+	}
+
+	pub fn si_suffixes(mut self) -> NumberFormat {
+		self.si_suffixes = true;
+
+		self
+	}
+
+	pub fn prefix(mut self, prefix: &str) -> NumberFormat {
+		self.prefix = prefix.to_string();
+
+		self
+	}
+
+	pub fn suffix(mut self, suffix: &str) -> NumberFormat {
+		self.suffix = suffix.to_string();
+
+		self
+	}
+
+	/// Formats a standalone number
+	pub fn format(&self, value: f64) -> String {
+		self.format_with(value, None)
+	}
+
+	/// Formats a number that is part of a series spaced `tick` apart, e.g. grid lines
+	pub fn format_tick(&self, value: f64, tick: f64) -> String {
+		self.format_with(value, Some(tick))
+	}
+
+	/// Digits of the (positive) number to the precision, without separators
+	fn digits(&self, scaled: f64, unit_factor: f64, tick: Option<f64>) -> String {
+		match (self.precision, tick) {
+			(Precision::Fixed(decimals), _) => format!("{:.*}", decimals, scaled),
+			(Precision::Tick(tick_size), _) if tick_size > 0. && tick_size.is_finite() => {
+				format!("{:.*}", tick_decimals(tick_size / unit_factor), scaled)
+			}
+			(_, Some(tick)) if tick > 0. && tick.is_finite() => format!("{:.*}", tick_decimals(tick / unit_factor), scaled),
+			_ => trim_zeros(format!("{:.*}", significant_decimals(scaled, 5), scaled)),
+		}
+	}
+
+	fn format_with(&self, value: f64, tick: Option<f64>) -> String {
+		if !value.is_finite() {
+			return format!("{}{}{}", self.prefix, value, self.suffix);
+		}
+
+		let mut digits = self.digits(value.abs(), 1., tick);
+		let mut unit = "";
+
+		if self.si_suffixes {
+			// Units are picked by the rounded number, so e.g. 999,999 becomes 1M rather than 1000K
+			let mut unit_factor = 1.;
+
+			for &(factor, si_unit) in SI_UNITS.iter().rev() {
+				if digits.parse::<f64>().unwrap_or(0.) * unit_factor >= factor {
+					digits = self.digits(value.abs() / factor, factor, tick);
+					unit = si_unit;
+					unit_factor = factor;
+				}
+			}
+		}
+
+		let digits = match self.thousands_separator {
+			Some(separator) => group_thousands(&digits, separator),
+			None => digits,
+		};
+
+		// Don't show a sign for numbers that are rounded to zero
+		let sign = if value < 0. && digits.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+
+		format!("{}{}{}{}{}", sign, self.prefix, digits, unit, self.suffix)
+	}
+}
+
+/// Decimal places needed to display multiples of the tick exactly
+fn tick_decimals(tick: f64) -> usize {
+	for decimals in 0..MAX_DECIMALS {
+		let shifted = tick * 10_f64.powi(decimals as i32);
+
+		if (shifted - shifted.round()).abs() < 1e-6 * shifted.max(1.) {
+			return decimals;
+		}
+	}
+
+	MAX_DECIMALS
+}
+
+/// Decimal places needed to display the significant digits of the (positive) number
+fn significant_decimals(value: f64, significant_digits: i32) -> usize {
+	if value == 0. {
+		return 0;
+	}
+
+	(significant_digits - 1 - value.log10().floor() as i32).max(0).min(MAX_DECIMALS as i32) as usize
+}
+
+fn trim_zeros(digits: String) -> String {
+	if digits.contains('.') {
+		digits.trim_end_matches('0').trim_end_matches('.').to_string()
+	} else {
+		digits
+	}
+}
+
+fn group_thousands(digits: &str, separator: char) -> String {
+	let (integer, fraction) = match digits.find('.') {
+		Some(dot) => digits.split_at(dot),
+		None => (digits, ""),
+	};
+
+	let mut grouped = String::with_capacity(digits.len() + integer.len() / 3);
+
+	for (i, c) in integer.chars().enumerate() {
+		if i > 0 && (integer.len() - i) % 3 == 0 {
+			grouped.push(separator);
+		}
+		grouped.push(c);
+	}
+
+	grouped + fraction
+}
+
+#[cfg(test)]
+#[test]
+fn auto_precision_test() {
+	let format = NumberFormat::new();
+
+	assert_eq!(format.format(48213.), "48213");
+	assert_eq!(format.format(7630.8), "7630.8");
+	assert_eq!(format.format(0.00038312), "0.00038312");
+	assert_eq!(format.format(0.), "0");
+	assert_eq!(format.format_tick(6000., 200.), "6000");
+	assert_eq!(format.format_tick(0.00034, 0.00002), "0.00034");
+	assert_eq!(format.format_tick(2.5, 0.25), "2.50");
+}
+
+#[cfg(test)]
+#[test]
+fn fixed_precision_test() {
+	let format = NumberFormat::new().decimals(2);
+
+	assert_eq!(format.format(48213.), "48213.00");
+	assert_eq!(format.format_tick(0.00038312, 0.00001), "0.00");
+	assert_eq!(format.format(-0.001), "0.00");
+}
+
+#[cfg(test)]
+#[test]
+fn tick_precision_test() {
+	let format = NumberFormat::new().tick_size(0.01);
+
+	assert_eq!(format.format(48213.57), "48213.57");
+	assert_eq!(format.format(48213.), "48213.00");
+	assert_eq!(format.format_tick(6000., 200.), "6000.00");
+	assert_eq!(NumberFormat::new().tick_size(0.1).format(0.36), "0.4");
+	assert_eq!(NumberFormat::new().tick_size(0.5).format(7630.5), "7630.5");
+	assert_eq!(NumberFormat::new().tick_size(1000.).si_suffixes().format(48_213_000.), "48.213M");
+
+	// Auto precision shows 5 significant digits of the same price
+	assert_eq!(NumberFormat::new().format(48213.57), "48214");
+}
+
+#[cfg(test)]
+#[test]
+fn decorations_test() {
+	let format = NumberFormat::new().decimals(2).thousands_separator(',').prefix("$").suffix(" USD");
+
+	assert_eq!(format.format(1234567.891), "$1,234,567.89 USD");
+	assert_eq!(format.format(-123.4), "-$123.40 USD");
+	assert_eq!(format.format(f64::NAN), "$NaN USD");
+}
+
+#[cfg(test)]
+#[test]
+fn si_suffixes_test() {
+	let format = NumberFormat::new().si_suffixes();
+
+	assert_eq!(format.format(48200.), "48.2K");
+	assert_eq!(format.format(1500000.), "1.5M");
+	assert_eq!(format.format(2e9), "2B");
+	assert_eq!(format.format(999.), "999");
+	assert_eq!(format.format_tick(48000., 1000.), "48K");
+
+	// Rounding up to the next unit
+	assert_eq!(format.format(999_999.), "1M");
+	assert_eq!(format.format(999_960.), "999.96K");
+	assert_eq!(format.format(999.9999), "1K");
+	assert_eq!(format.format(-999_999_999.), "-1B");
+	assert_eq!(NumberFormat::new().decimals(0).si_suffixes().format(999_600.), "1M");
+}
@@ -0,0 +1,50 @@
+use std::slice;
+
+use data::Candle;
+use utils::{aggregate, SetAggregate};
+
+/// Heikin-Ashi candles of the candles, which average out the noise of the prices to show trends.
+/// Volumes and timestamps stay those of the real candles.
+pub fn heikin_ashi<C: Candle, D: From<SetAggregate>>(data: &[C]) -> Vec<D> {
+	let mut previous: Option<(f64, f64)> = None;
+
+	data.iter()
+		.map(|candle| {
+			let close = (candle.open() + candle.high() + candle.low() + candle.close()) / 4.;
+			let open = match previous {
+				Some((open, close)) => (open + close) / 2.,
+				None => (candle.open() + candle.close()) / 2.,
+			};
+			previous = Some((open, close));
+
+			let mut set = aggregate(slice::from_ref(candle));
+			set.o = open;
+			set.h = candle.high().max(open).max(close);
+			set.l = candle.low().min(open).min(close);
+			set.c = close;
+
+			set.into()
+		})
+		.collect()
+}
+
+#[cfg(test)]
+#[test]
+fn heikin_ashi_test() {
+	use model::data::OHLC;
+
+	let data = vec![
+		OHLC { o: 10., h: 12., l: 9., c: 11., t: Some(0) },
+		OHLC { o: 11., h: 15., l: 11., c: 14., t: Some(60) },
+		OHLC { o: 14., h: 14., l: 8., c: 9., t: Some(120) },
+	];
+
+	let candles: Vec<OHLC> = heikin_ashi(&data);
+
+	assert_eq!(candles, vec![
+		OHLC { o: 10.5, h: 12., l: 9., c: 10.5, t: Some(0) },
+		OHLC { o: 10.5, h: 15., l: 10.5, c: 12.75, t: Some(60) },
+		OHLC { o: 11.625, h: 14., l: 8., c: 11.25, t: Some(120) },
+	]);
+	assert!(heikin_ashi::<OHLC, OHLC>(&[]).is_empty());
+}
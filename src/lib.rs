@@ -1,3 +1,5 @@
+extern crate ab_glyph;
+extern crate chrono;
 extern crate image;
 #[macro_use]
 extern crate log;
@@ -7,12 +9,24 @@ extern crate serde_derive;
 extern crate tempdir;
 
 use std::boxed::Box;
+use std::fs;
+use std::io::Write;
 use std::path::*;
+use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use image::png::PNGEncoder;
 use tempdir::*;
 
 pub use data::*;
+pub use error::{CandleField, OhlcError};
+pub use format::{NumberFormat, Precision};
+pub use heikin_ashi::heikin_ashi;
+pub use loader::{parse_timestamp, Column, CsvLoader, TimestampFormat};
+pub use resample::{resample, Resampling};
+pub use theme::Theme;
+pub use ticks::{candles_from_ticks, Bars, CandleBuilder, Side, Tick};
+pub use validation::{validate, ValidationPolicy, Violation, ViolationKind};
 use model::*;
 use model::painting::Point;
 use model::rex::*;
@@ -23,18 +37,35 @@ pub mod fonts {
 }
 
 pub mod data;
+pub mod error;
+pub mod format;
+pub mod heikin_ashi;
+pub mod loader;
 pub mod model;
+pub mod resample;
 #[cfg(test)]
 mod tests;
+pub mod theme;
+pub mod ticks;
 pub mod utils;
+pub mod validation;
 
-/// OHLC Chart Configuration, mutate through the methods
-#[derive(Serialize, Deserialize)]
+/// OHLC Chart Configuration, mutate through the methods, fields missing from deserialized configurations keep their
+/// default values
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default, bound(deserialize = "C: Candle"))]
 pub struct OHLCRenderOptions<C> {
 	/// Title of the chart
 	pub title: String,
 	/// Colour for the title of the chart
 	pub title_colour: u32,
+	/// Line height of the title in pixels, the title uses the size of the other text if this is absent
+	pub title_font_size: Option<f32>,
+	/// Font of all text on the chart
+	#[serde(skip)]
+	pub font: Font,
+	/// Colours for the legend and for extensions created without colours, the other colour fields take precedence
+	pub theme: Theme,
 	/// Background tint of the entire chart (the tint is the value for all of R, G and B)
 	pub background_colour: u32,
 	/// Colour for the "current value" dot and line across the chart
@@ -43,17 +74,65 @@ pub struct OHLCRenderOptions<C> {
 	pub time_units: u64,
 	/// Colour for axes labels and grid lines
 	pub line_colour: u32,
+	/// How the grid lines are drawn
+	pub grid_stroke: Stroke,
+	/// How the high, low and current value lines are drawn
+	pub indicative_stroke: Stroke,
 	/// Intervals for drawing price lines in currency units
 	pub price_line_interval: f64,
 	/// Intervals for time lines in time_units
 	pub time_line_interval: i64,
+	/// Target spacing of automatically picked line intervals (see `auto_line`), the fixed intervals are used if this is absent
+	pub auto_grid: Option<GridDensity>,
+	/// How the time lines are labelled
+	pub time_labels: TimeLabels,
+	/// How candles carrying timestamps are laid out along the time axis
+	pub time_axis: TimeAxis,
+	/// How prices are mapped onto the vertical axis of the main chart
+	pub price_scale: PriceScale,
+	/// Whether lines, shapes and outline font text of PNG charts are drawn with anti-aliased edges instead of whole pixels
+	pub antialias: bool,
+	/// Whether SVG charts ask viewers to keep the edges of lines crisp rather than anti-aliasing them as they do by default
+	pub svg_crisp_edges: bool,
+	/// Room above the highest price of the main chart
+	pub price_padding_top: PricePadding,
+	/// Room below the lowest price of the main chart
+	pub price_padding_bottom: PricePadding,
+	/// Lowest and highest price of the main chart, the range is fitted to the candles if this is absent
+	pub fixed_price_range: Option<(f64, f64)>,
+	/// Whether the fitted range makes room for overlays such as Bollinger bands and moving averages
+	pub include_indicators_in_range: bool,
+	/// Whether the candles are drawn as Heikin-Ashi candles, indicators and the current value keep the real prices
+	pub heikin_ashi: bool,
+	/// How the candles are drawn
+	pub candle_style: CandleStyle,
+	/// What happens to candles that don't make sense
+	pub validation_policy: ValidationPolicy,
+	/// Whether rendering fails with `OhlcError::EmptyData` when no candles are left, instead of drawing "No data"
+	pub reject_empty_data: bool,
+	/// Formatting of prices in labels
+	pub price_format: NumberFormat,
+	/// Formatting of volumes in labels
+	pub volume_format: NumberFormat,
 	/// RGBA(8) Colour for when the OHLC indicates fall
 	pub down_colour: u32,
 	/// RGBA(8) Colour for when the OHLC indicates rise
 	pub up_colour: u32,
-	/// Additional rendering extensions
+	/// Width of the main chart in pixels, extension strips are appended below it
+	pub width: usize,
+	/// Height of the main chart in pixels, excluding extension strips
+	pub height: usize,
+	/// Space reserved around the plotting area for the title, lores and axis labels
+	pub margin: Margin,
+	/// Additional rendering extensions, shared by copies of the options
 	#[serde(skip)]
-	pub(crate) render_extensions: Vec<Box<dyn RendererExtension<Candle=C>>>,
+	pub(crate) render_extensions: Vec<Rc<dyn RendererExtension<Candle=C>>>,
+}
+
+impl<C: Candle> Default for OHLCRenderOptions<C> {
+	fn default() -> OHLCRenderOptions<C> {
+		OHLCRenderOptions::new()
+	}
 }
 
 impl<C: Candle> OHLCRenderOptions<C> {
@@ -62,15 +141,44 @@ impl<C: Candle> OHLCRenderOptions<C> {
 		OHLCRenderOptions {
 			title: String::new(),
 			title_colour: 0,
+			title_font_size: None,
+			font: Font::bitmap(),
+			theme: Theme::light(),
 			background_colour: 0xDDDDDDFF,
 			current_value_colour: 0x2E44EAFF,
 			// Default is 1 hour
 			time_units: 3600,
 			line_colour: 0xFFFFFFAA,
+			grid_stroke: Stroke::new(),
+			indicative_stroke: Stroke::new(),
 			price_line_interval: 1.0,
 			time_line_interval: 24,
+			auto_grid: None,
+			time_labels: TimeLabels::Relative,
+			time_axis: TimeAxis::Continuous,
+			price_scale: PriceScale::Linear,
+			antialias: false,
+			svg_crisp_edges: false,
+			price_padding_top: PricePadding::Percent(0.),
+			price_padding_bottom: PricePadding::Percent(0.),
+			fixed_price_range: None,
+			include_indicators_in_range: false,
+			heikin_ashi: false,
+			candle_style: CandleStyle::Filled,
+			validation_policy: ValidationPolicy::Reject,
+			reject_empty_data: false,
+			price_format: NumberFormat::new(),
+			volume_format: NumberFormat::new().si_suffixes(),
 			down_colour: 0xD33040FF,
 			up_colour: 0x27A819FF,
+			width: 1310,
+			height: 650,
+			margin: Margin {
+				top: 60,
+				bottom: 35,
+				left: 12,
+				right: 113,
+			},
 			render_extensions: vec![],
 		}
 	}
@@ -82,6 +190,20 @@ impl<C: Candle> OHLCRenderOptions<C> {
 		self
 	}
 
+	/// Sets the font of all text, e.g. `Font::embedded(16.)` or `Font::from_bytes(bytes, 16.)`
+	pub fn font(&mut self, font: Font) -> &mut Self {
+		self.font = font;
+
+		self
+	}
+
+	/// Sets the line height of the title in pixels, which only applies to TrueType/OpenType fonts
+	pub fn title_font_size(&mut self, size: f32) -> &mut Self {
+		self.title_font_size = Some(size);
+
+		self
+	}
+
 	pub fn indicator_colours(&mut self, current_val: u32, down: u32, up: u32) -> &mut Self {
 		self.current_value_colour = current_val;
 		self.down_colour = down;
@@ -94,6 +216,151 @@ impl<C: Candle> OHLCRenderOptions<C> {
 		self.line_colour = colour;
 		self.price_line_interval = price_interval;
 		self.time_line_interval = time_interval as i64;
+		self.auto_grid = None;
+
+		self
+	}
+
+	/// Pick human-friendly line intervals that keep the lines about the amount of pixels specified apart
+	pub fn auto_line(&mut self, colour: u32, price_spacing: usize, time_spacing: usize) -> &mut Self {
+		self.line_colour = colour;
+		self.auto_grid = Some(GridDensity { price_spacing, time_spacing });
+
+		self
+	}
+
+	/// Label time lines with the candles' timestamps, formatted strftime-style in the UTC offset (in seconds) provided
+	pub fn absolute_time_labels(&mut self, format: &str, utc_offset: i32) -> &mut Self {
+		self.time_labels = TimeLabels::Absolute { format: format.to_string(), utc_offset };
+
+		self
+	}
+
+	pub fn time_axis(&mut self, time_axis: TimeAxis) -> &mut Self {
+		self.time_axis = time_axis;
+
+		self
+	}
+
+	/// Lays out candles built from trades by the bars provided, time bars by their windows and the others next to each
+	/// other as they cover irregular amounts of time. Time since the right edge means nothing for the latter, so they're
+	/// labelled with their UTC times unless absolute labels are set already.
+	pub fn bars(&mut self, bars: Bars) -> &mut Self {
+		match bars {
+			Bars::Time(length) => {
+				self.time_units = length.max(1) as u64;
+				self.time_axis = TimeAxis::Continuous;
+			}
+			_ => {
+				self.time_axis = TimeAxis::Sequential;
+
+				if self.time_labels == TimeLabels::Relative {
+					self.absolute_time_labels("%H:%M", 0);
+				}
+			}
+		}
+
+		self
+	}
+
+	pub fn price_scale(&mut self, price_scale: PriceScale) -> &mut Self {
+		self.price_scale = price_scale;
+
+		self
+	}
+
+	/// Leaves room above the highest and below the lowest price, e.g. `PricePadding::Percent(5.)`
+	pub fn price_padding(&mut self, top: PricePadding, bottom: PricePadding) -> &mut Self {
+		self.price_padding_top = top;
+		self.price_padding_bottom = bottom;
+
+		self
+	}
+
+	/// Shows the prices from min to max no matter the candles, prices outside of it stick to the edges
+	pub fn fixed_price_range(&mut self, min: f64, max: f64) -> &mut Self {
+		self.fixed_price_range = Some((min, max));
+
+		self
+	}
+
+	pub fn include_indicators_in_range(&mut self, include: bool) -> &mut Self {
+		self.include_indicators_in_range = include;
+
+		self
+	}
+
+	pub fn heikin_ashi(&mut self, heikin_ashi: bool) -> &mut Self {
+		self.heikin_ashi = heikin_ashi;
+
+		self
+	}
+
+	pub fn candle_style(&mut self, style: CandleStyle) -> &mut Self {
+		self.candle_style = style;
+
+		self
+	}
+
+	pub fn grid_stroke(&mut self, stroke: Stroke) -> &mut Self {
+		self.grid_stroke = stroke;
+
+		self
+	}
+
+	pub fn indicative_stroke(&mut self, stroke: Stroke) -> &mut Self {
+		self.indicative_stroke = stroke;
+
+		self
+	}
+
+	pub fn antialias(&mut self, antialias: bool) -> &mut Self {
+		self.antialias = antialias;
+
+		self
+	}
+
+	pub fn svg_crisp_edges(&mut self, crisp_edges: bool) -> &mut Self {
+		self.svg_crisp_edges = crisp_edges;
+
+		self
+	}
+
+	/// Sets whether invalid candles fail the render, are left out or are repaired
+	pub fn validation_policy(&mut self, policy: ValidationPolicy) -> &mut Self {
+		self.validation_policy = policy;
+
+		self
+	}
+
+	/// Sets whether charts without candles, e.g. after invalid ones are skipped, fail instead of saying "No data"
+	pub fn reject_empty_data(&mut self, reject: bool) -> &mut Self {
+		self.reject_empty_data = reject;
+
+		self
+	}
+
+	pub fn price_format(&mut self, format: NumberFormat) -> &mut Self {
+		self.price_format = format;
+
+		self
+	}
+
+	pub fn volume_format(&mut self, format: NumberFormat) -> &mut Self {
+		self.volume_format = format;
+
+		self
+	}
+
+	/// Takes all colours from the theme, colours set afterwards override it
+	pub fn theme(&mut self, theme: Theme) -> &mut Self {
+		self.background_colour = theme.background;
+		self.title_colour = theme.text;
+		self.line_colour = theme.grid;
+		self.up_colour = theme.up;
+		self.down_colour = theme.down;
+		self.current_value_colour = theme.current_value;
+		self.theme = theme;
 
 		self
 	}
@@ -104,6 +371,19 @@ impl<C: Candle> OHLCRenderOptions<C> {
 		self
 	}
 
+	pub fn dimensions(&mut self, width: usize, height: usize) -> &mut Self {
+		self.width = width;
+		self.height = height;
+
+		self
+	}
+
+	pub fn margin(&mut self, top: usize, bottom: usize, left: usize, right: usize) -> &mut Self {
+		self.margin = Margin { top, bottom, left, right };
+
+		self
+	}
+
 	pub fn time_units(&mut self, time_units: u64) -> &mut Self {
 		self.time_units = time_units;
 
@@ -111,13 +391,13 @@ impl<C: Candle> OHLCRenderOptions<C> {
 	}
 
 	pub fn add_extension<RE: RendererExtension<Candle=C> + 'static>(&mut self, extension: RE) -> &mut Self {
-		self.render_extensions.push(Box::new(extension));
+		self.render_extensions.push(Rc::new(extension));
 
 		self
 	}
 
 	pub fn add_extensions(&mut self, extensions: Vec<Box<dyn RendererExtension<Candle=C>>>) -> &mut Self {
-		self.render_extensions.extend(extensions);
+		self.render_extensions.extend(extensions.into_iter().map(Rc::from));
 
 		self
 	}
@@ -126,85 +406,204 @@ impl<C: Candle> OHLCRenderOptions<C> {
 	///
 	/// Takes a lambda function for processing the image once it's rendered, do not do anything asynchronous with the image as it will be deleted as soon as the function finishes.
 	///
-	/// Returns an error string originating from OHLC if an error occurs, and the result of the callback function otherwise.
-	pub fn render<F, R>(&self, data: Vec<C>, callback: F) -> Result<R, String>
+	/// Returns an error originating from OHLC if an error occurs, and the result of the callback function otherwise.
+	pub fn render<F, R>(&self, data: Vec<C>, callback: F) -> Result<R, OhlcError>
 		where F: Fn(&Path) -> R + Sized {
 		// Create temporary directory
-		if let Ok(dir) = TempDir::new(&format!("ohlc_render_{}", SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos())) {
-			let file_path = dir.path().join("chart.png");
+		let dir = TempDir::new(&format!("ohlc_render_{}", SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos()))?;
+		let file_path = dir.path().join("chart.png");
 
-			let result = match self.render_and_save(data, &file_path) {
-				Ok(_) => Ok((callback)(&file_path)),
-				Err(err) => Err(err)
-			};
+		let result = match self.render_and_save(data, &file_path) {
+			Ok(_) => Ok((callback)(&file_path)),
+			Err(err) => Err(err)
+		};
+
+		let _ = dir.close(); // Delete temporary directory
 
-			let _ = dir.close(); // Delete temporary directory
+		result
+	}
 
-			result
+	/// Renders the chart and saves it to the specified path
+	///
+	/// Returns an error if an error occurs
+	///
+	/// Paths with an `.svg` extension are saved as SVG; PNG, JPEG, BMP, PPM and ICO are rasterised. Other extensions are
+	/// rejected before anything is rendered or written.
+	pub fn render_and_save(&self, data: Vec<C>, path: &Path) -> Result<(), OhlcError> {
+		let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("").to_ascii_lowercase();
+
+		match &*extension {
+			"svg" => {
+				let svg = self.render_to_svg(data)?;
+
+				Ok(fs::write(path, svg)?)
+			}
+			"png" | "jpg" | "jpeg" | "bmp" | "ppm" | "ico" => {
+				let image = self.render_to_rgb_buffer(data)?;
+
+				// File save occurs here
+				image::save_buffer(path, &image.buffer[..], image.width as u32, image.height as u32, image::RGB(8))
+					.map_err(OhlcError::from_encoder)
+			}
+			_ => Err(OhlcError::Encoding(format!("Unsupported image format {:?}", extension))),
+		}
+	}
+
+	/// Renders the chart and returns it PNG encoded, without touching the filesystem
+	///
+	/// Returns an error if an error occurs
+	pub fn render_to_png_bytes(&self, data: Vec<C>) -> Result<Vec<u8>, OhlcError> {
+		let mut bytes = vec![];
+
+		self.render_to_writer(data, &mut bytes)?;
+
+		Ok(bytes)
+	}
+
+	/// Renders the chart and writes it PNG encoded into the writer
+	///
+	/// Returns an error if an error occurs
+	pub fn render_to_writer<W: Write>(&self, data: Vec<C>, writer: W) -> Result<(), OhlcError> {
+		let start_time = SystemTime::now();
+
+		let image = self.render_to_rgb_buffer(data)?;
+
+		if let Err(err) = PNGEncoder::new(writer).encode(&image.buffer[..], image.width as u32, image.height as u32, image::RGB(8)) {
+			Err(OhlcError::from_encoder(err))
 		} else {
-			Err("Failed to create a temporary directory.".to_string())
+			debug!("Chart PNG compression finished in {:?}", start_time.elapsed());
+
+			Ok(())
 		}
 	}
 
-	/// Renders the chart and saves it to the specified path
+	/// Renders the chart into raw RGB(8) pixels, extension strips included
 	///
-	/// Returns an error string if an error occurs
-	pub fn render_and_save(&self, data: Vec<C>, path: &Path) -> Result<(), String> {
+	/// Returns an error if an error occurs
+	pub fn render_to_rgb_buffer(&self, data: Vec<C>) -> Result<RgbBuffer, OhlcError> {
+		let raster = self.render_to_surface(data, |width, height, background| RasterSurface::new(width, height, background).antialiased(self.antialias).with_font(self.font.clone()))?;
+
+		Ok(raster.into_rgb_buffer())
+	}
+
+	/// Renders the chart as an SVG document, extension strips included
+	///
+	/// Returns an error if an error occurs
+	pub fn render_to_svg(&self, data: Vec<C>) -> Result<String, OhlcError> {
+		let vector = self.render_to_surface(data, |width, height, background| VectorSurface::new(width, height, background).antialiased(!self.svg_crisp_edges).with_font(self.font.clone()))?;
+
+		Ok(vector.to_svg())
+	}
+
+	/// Runs the whole render pipeline on the surface the function provided creates out of the width, height and
+	/// background colour of the chart, e.g. `|width, height, background| RasterSurface::new(width, height, background)`,
+	/// and returns the surface with the chart and its extension strips drawn onto it
+	///
+	/// Returns an error if an error occurs
+	pub fn render_to_surface<S, F>(&self, data: Vec<C>, create_surface: F) -> Result<S, OhlcError>
+		where S: DrawingSurface, F: FnOnce(usize, usize, u32) -> S {
 		let start_time = SystemTime::now();
 
-		if let Err(err) = validate(&data) {
-			return Err(format!("Data validation error: {}", err));
+		let data = self.validation_policy.apply(data)?;
+
+		if data.is_empty() && self.reject_empty_data {
+			return Err(OhlcError::EmptyData);
 		}
 
 		#[cfg(test)] {
 			debug!("Validated input data @ {:?}", start_time.elapsed());
 		}
 
-		let ohlc_of_set = aggregate(&data[..]);
+		let margin = self.margin;
 
-		let margin = Margin {
-			top: 60,
-			bottom: 35,
-			left: 12,
-			right: 113,
-		};
+		if margin.top + margin.bottom >= self.height || margin.left + margin.right >= self.width {
+			return Err(OhlcError::Layout("Margins leave no room for the chart.".to_string()));
+		}
 
-		let width = 1310;
-		let height = 650;
+		if self.price_scale == PriceScale::Logarithmic {
+			if let Some(index) = data.iter().position(|candle| candle.low() <= 0.) {
+				return Err(OhlcError::Validation { index, field: CandleField::Low, kind: ViolationKind::NotPositive });
+			}
+		}
+
+		let (max_price, min_price) = self.price_range(&data[..]);
 
 		#[cfg(test)] {
 			debug!("Allocated vector @ {:?}", start_time.elapsed());
 		}
 
-		let mut chart_buffer = ChartBuffer::new(width, height, margin, ohlc_of_set.h, ohlc_of_set.l, (self.time_units * data.len() as u64) as i64, self.background_colour);
+		let time_scale = TimeScale::new(&data[..], self.time_units as i64, self.time_axis);
+		let mut surface = create_surface(self.width, self.height, self.background_colour);
+		let mut chart_buffer = ChartBuffer::new(&mut surface, margin, max_price, min_price, self.price_scale, time_scale, self.background_colour)?;
+		chart_buffer.theme = Theme {
+			background: self.background_colour,
+			grid: self.line_colour,
+			up: self.up_colour,
+			down: self.down_colour,
+			current_value: self.current_value_colour,
+			..self.theme.clone()
+		};
+		chart_buffer.price_format = self.price_format.clone();
+		chart_buffer.volume_format = self.volume_format.clone();
 
 		#[cfg(test)] {
 			debug!("Allocated image and populated background @ {:?}", start_time.elapsed());
 		}
 
-		GridLines::new(
-			self.line_colour,
-			true,
-			self.price_line_interval,
-			self.time_line_interval * self.time_units as i64).apply(&mut chart_buffer, &data[..]);
+		if data.is_empty() {
+			// Without prices there's nothing to mark on the axes, so the plotting area only says so
+			let centre = ((margin.left + self.width - margin.right) / 2, (margin.top + self.height - margin.bottom) / 2);
+			let colour = chart_buffer.theme.text;
+			chart_buffer.text_anchored(centre, "No data", colour, Anchor::CENTRE);
+		} else {
+			let mut grid_lines = GridLines::new(
+				self.line_colour,
+				true,
+				self.price_line_interval,
+				self.time_line_interval * self.time_units as i64)
+				.time_labels(self.time_labels.clone())
+				.stroke(self.grid_stroke);
+
+			if let Some(density) = self.auto_grid {
+				grid_lines = grid_lines.auto_intervals(density);
+			}
 
-		#[cfg(test)] {
-			debug!("Rendered grid lines @ {:?}", start_time.elapsed());
-		}
+			grid_lines.apply(&mut chart_buffer, &data[..]);
 
-		OHLCCandles::new(self.up_colour, self.down_colour).apply(&mut chart_buffer, &data[..]);
+			#[cfg(test)] {
+				debug!("Rendered grid lines @ {:?}", start_time.elapsed());
+			}
 
-		#[cfg(test)] {
-			debug!("Rendered candles @ {:?}", start_time.elapsed());
-		}
+			if self.heikin_ashi {
+				let candles: Vec<SetAggregate> = heikin_ashi(&data[..]);
+				OHLCCandles::new(self.up_colour, self.down_colour).style(self.candle_style).apply(&mut chart_buffer, &candles[..]);
+			} else {
+				OHLCCandles::new(self.up_colour, self.down_colour).style(self.candle_style).apply(&mut chart_buffer, &data[..]);
+			}
 
-		BasicIndicativeLines::new(self.up_colour, self.down_colour, self.current_value_colour).apply(&mut chart_buffer, &data[..]);
+			#[cfg(test)] {
+				debug!("Rendered candles @ {:?}", start_time.elapsed());
+			}
 
-		#[cfg(test)] {
-			debug!("Rendered basic indicator lines @ {:?}", start_time.elapsed());
+			BasicIndicativeLines::new(self.up_colour, self.down_colour, self.current_value_colour)
+				.stroke(self.indicative_stroke)
+				.apply(&mut chart_buffer, &data[..]);
+
+			#[cfg(test)] {
+				debug!("Rendered basic indicator lines @ {:?}", start_time.elapsed());
+			}
+		}
+
+		if let Some(size) = self.title_font_size {
+			let title_font = chart_buffer.surface().font().sized(size);
+			chart_buffer.surface().set_font(title_font);
 		}
 
-		chart_buffer.text((8, 8), &self.title, self.title_colour);
+		// Keep the title inside the top margin on short charts
+		let title_height = chart_buffer.surface().font().line_height();
+		let title_y = 8.min(margin.top.saturating_sub(title_height) / 2);
+		chart_buffer.text((8, title_y), &self.title, self.title_colour);
+		chart_buffer.surface().set_font(self.font.clone());
 
 		#[cfg(test)] {
 			debug!("Added title text @ {:?}", start_time.elapsed());
@@ -212,20 +611,28 @@ impl<C: Candle> OHLCRenderOptions<C> {
 
 		let mut lore_cursor: Point = (chart_buffer.margin.left + 4, chart_buffer.margin.top + 4);
 
-		for ext in &self.render_extensions {
+		let mut palette_index = 0;
+
+		for ext in self.render_extensions.iter() {
+			chart_buffer.palette_index = palette_index;
 			ext.apply(&mut chart_buffer, &data[..]);
 
-			if let Some(rgba) = ext.lore_colour() {
-				chart_buffer.text_with_background(lore_cursor, &ext.name(), rgba, 0x7F7F7F7F);
+			if let Some(rgba) = ext.themed_lore_colour(&chart_buffer.theme, palette_index) {
+				let legend_background = chart_buffer.theme.legend_background;
+				chart_buffer.text_with_background(lore_cursor, &ext.name(), rgba, legend_background);
 
-				lore_cursor.1 += 17; // Move down 1 row, 17 is the char height
+				lore_cursor.1 += chart_buffer.measure_text(&ext.name()).1;
 			}
 
+			palette_index += ext.palette_slots();
+
 			#[cfg(test)] {
 				debug!("Rendered extension: {} @ {:?}", ext.name(), start_time.elapsed());
 			}
 		}
 
+		chart_buffer.draw_price_labels();
+
 		#[cfg(test)] {
 			debug!("Rendered extensions lores @ {:?}", start_time.elapsed());
 		}
@@ -234,41 +641,61 @@ impl<C: Candle> OHLCRenderOptions<C> {
 			debug!("Completed all rendering @ {:?}", start_time.elapsed());
 		}
 
-		// File save occurs here
-		if let Err(err) = image::save_buffer(path, &chart_buffer.buffer[..], chart_buffer.width() as u32, chart_buffer.height() as u32, image::RGB(8)) {
-			Err(format!("Image write error: {:?}", err))
-		} else {
-			#[cfg(test)] {
-				debug!("Chart PNG compression finished {:?}", start_time.elapsed());
-			}
+		debug!("Chart rendered in {:?}", start_time.elapsed());
 
-			debug!("Chart rendered in {:?}", start_time.elapsed());
+		Ok(surface)
+	}
 
-			Ok(())
+	/// The highest and lowest price of the main chart
+	fn price_range(&self, data: &[C]) -> (f64, f64) {
+		if let Some((min, max)) = self.fixed_price_range {
+			return (max, min);
 		}
+
+		// Any positive price works for the empty price range of an empty chart
+		if data.is_empty() {
+			return (1., 1.);
+		}
+
+		// Heikin-Ashi candles reach at least as far as the real ones
+		let ohlc_of_set = if self.heikin_ashi { aggregate(&heikin_ashi::<C, SetAggregate>(data)) } else { aggregate(data) };
+		let (mut high, mut low) = (ohlc_of_set.h, ohlc_of_set.l);
+
+		if self.include_indicators_in_range {
+			for (ext_low, ext_high) in self.render_extensions.iter().filter_map(|ext| ext.price_range(data)) {
+				high = high.max(ext_high);
+
+				// e.g. lower Bollinger bands dip below zero on volatile data, which logarithmic scales can't show
+				if ext_low > 0. || self.price_scale == PriceScale::Linear {
+					low = low.min(ext_low);
+				}
+			}
+		}
+
+		(
+			self.price_padding_top.apply(high, high, low, true, self.price_scale),
+			self.price_padding_bottom.apply(low, high, low, false, self.price_scale),
+		)
 	}
 }
 
-fn validate<C: Candle>(data: &Vec<C>) -> Result<(), &'static str> {
-	for elem in data {
-		let open = elem.open();
-		let high = elem.high();
-		let close = elem.close();
-		let low = elem.low();
-
-		return if open > high {
-			Err("Opening value is higher than high value.")
-		} else if close > high {
-			Err("Closing value is higher than high value.")
-		} else if low > high {
-			Err("Low value is higher than high value.")
-		} else if open < low {
-			Err("Opening value is lower than low value.")
-		} else if close < low {
-			Err("Closing value is lower than low value.")
+impl<C: Candle + Clone + From<SetAggregate>> OHLCRenderOptions<C> {
+	/// Groups the candles into candles of the period in seconds, e.g. 1-minute candles into an hourly chart, and renders
+	/// them with the function provided, e.g. `|options, candles| options.render_to_png_bytes(candles)`.
+	///
+	/// Candles carrying timestamps are grouped by windows aligned to the period, the others by how many time units fit
+	/// into the period. The function gets a copy of the options with the period as their time units.
+	pub fn render_resampled<F, R>(&self, data: &[C], period: u64, render: F) -> R
+		where F: FnOnce(&Self, Vec<C>) -> R {
+		let resampling = if data.iter().all(|candle| candle.timestamp().is_some()) {
+			Resampling::Window(period as i64)
 		} else {
-			continue;
+			Resampling::Count((period / self.time_units.max(1)) as usize)
 		};
+
+		let mut options = self.clone();
+		options.time_units = period;
+
+		render(&options, resample(data, resampling))
 	}
-	Ok(())
 }
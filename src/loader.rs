@@ -0,0 +1,316 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+use error::{CandleField, OhlcError};
+use model::data::OHLCV;
+
+/// Where a value of the candles is in the rows
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Column {
+	/// Position of the column, from 0
+	Index(usize),
+	/// Name of the column in the header, case-insensitive
+	Name(String),
+}
+
+impl From<usize> for Column {
+	fn from(index: usize) -> Column {
+		Column::Index(index)
+	}
+}
+
+impl<'a> From<&'a str> for Column {
+	fn from(name: &'a str) -> Column {
+		Column::Name(name.to_string())
+	}
+}
+
+/// How timestamps are written
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TimestampFormat {
+	UnixSeconds,
+	UnixMillis,
+	/// e.g. "2018-09-27T00:00:00Z", "2018-09-27 00:00:00" or "2018-09-27", taken as UTC without an offset
+	Iso8601,
+	/// Numbers above 10^11, which would be past the year 5000 in seconds, are milliseconds, other text is ISO 8601
+	Auto,
+}
+
+/// Column names of exchange exports that are recognised without a mapping
+const KNOWN_NAMES: [(CandleField, &[&str]); 7] = [
+	(CandleField::Timestamp, &["t", "time", "timestamp", "date", "datetime", "open_time", "opentime", "unix"]),
+	(CandleField::Open, &["o", "open"]),
+	(CandleField::High, &["h", "high"]),
+	(CandleField::Low, &["l", "low"]),
+	(CandleField::Close, &["c", "close"]),
+	(CandleField::Volume, &["v", "vol", "volume", "total_volume"]),
+	(CandleField::BuyVolume, &["bv", "buy_volume", "taker_buy_volume", "taker_buy_base_volume"]),
+];
+
+/// Reads candles out of CSV, mutate through the methods
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CsvLoader {
+	pub delimiter: char,
+	/// Whether the first row is a header, it's detected by having text that isn't a number or a timestamp if this is absent
+	pub header: Option<bool>,
+	pub timestamp_format: TimestampFormat,
+	/// Columns of the values, the others are found by their names in the header, or are timestamp, open, high, low,
+	/// close and volume in that order without a header (open, high, low and close for rows of 4)
+	pub columns: Vec<(CandleField, Column)>,
+}
+
+impl Default for CsvLoader {
+	fn default() -> CsvLoader {
+		CsvLoader::new()
+	}
+}
+
+impl CsvLoader {
+	/// Creates a loader of comma separated rows, with the header and timestamp format detected
+	pub fn new() -> CsvLoader {
+		CsvLoader {
+			delimiter: ',',
+			header: None,
+			timestamp_format: TimestampFormat::Auto,
+			columns: vec![],
+		}
+	}
+
+	pub fn delimiter(mut self, delimiter: char) -> CsvLoader {
+		self.delimiter = delimiter;
+
+		self
+	}
+
+	pub fn header(mut self, header: bool) -> CsvLoader {
+		self.header = Some(header);
+
+		self
+	}
+
+	pub fn timestamp_format(mut self, format: TimestampFormat) -> CsvLoader {
+		self.timestamp_format = format;
+
+		self
+	}
+
+	/// Reads the value from the column, e.g. `.column(CandleField::Volume, "Volume BTC")` or `.column(CandleField::Close, 4)`
+	pub fn column<T: Into<Column>>(mut self, field: CandleField, column: T) -> CsvLoader {
+		self.columns.retain(|&(mapped, _)| mapped != field);
+		self.columns.push((field, column.into()));
+
+		self
+	}
+
+	/// Reads the candles out of the file
+	pub fn load_path<P: AsRef<Path>>(&self, path: P) -> Result<Vec<OHLCV>, OhlcError> {
+		self.load(File::open(path)?)
+	}
+
+	/// Reads the candles out of the CSV, rows that are empty are skipped
+	pub fn load<R: Read>(&self, reader: R) -> Result<Vec<OHLCV>, OhlcError> {
+		let mut rows = vec![];
+
+		for (i, line) in BufReader::new(reader).lines().enumerate() {
+			let line = line?;
+
+			if !line.trim().is_empty() {
+				rows.push((i + 1, split_row(&line, self.delimiter)));
+			}
+		}
+
+		let header = match (self.header, rows.first()) {
+			(_, None) => return Ok(vec![]),
+			(Some(header), _) => header,
+			// Empty cells are optional values, not names
+			(None, Some((_, row))) => row.iter()
+				.any(|field| !field.is_empty() && field.parse::<f64>().is_err() && parse_timestamp(field, self.timestamp_format).is_none()),
+		};
+
+		let indices = if header {
+			let (line, names) = rows.remove(0);
+			self.indices(Some(&names), names.len()).map_err(|reason| OhlcError::Parse { line, reason })?
+		} else {
+			self.indices(None, rows[0].1.len()).map_err(|reason| OhlcError::Parse { line: rows[0].0, reason })?
+		};
+
+		rows.into_iter().map(|(line, row)| self.candle(&indices, &row).map_err(|reason| OhlcError::Parse { line, reason })).collect()
+	}
+
+	/// Positions of the columns of each field, in the order of `KNOWN_NAMES`
+	fn indices(&self, header: Option<&[String]>, width: usize) -> Result<Vec<Option<usize>>, String> {
+		// Rows of 4 have no timestamp
+		let skipped = if width < 5 { 1 } else { 0 };
+
+		KNOWN_NAMES.iter()
+			.enumerate()
+			.map(|(position, &(field, names))| {
+				let mapped = self.columns.iter().find(|&&(mapped, _)| mapped == field).map(|(_, column)| column);
+
+				match (mapped, header) {
+					(Some(&Column::Index(index)), _) => Ok(Some(index)),
+					(Some(Column::Name(name)), Some(header)) => header.iter()
+						.position(|column| column.eq_ignore_ascii_case(name))
+						.map(Some)
+						.ok_or_else(|| format!("There's no {:?} column for the {:?} value.", name, field)),
+					(Some(Column::Name(name)), None) => Err(format!("The {:?} column can't be found without a header.", name)),
+					(None, Some(header)) => Ok(header.iter().position(|column| names.iter().any(|name| column.eq_ignore_ascii_case(name)))),
+					(None, None) => Ok(if position >= skipped && position < 6 { Some(position - skipped) } else { None }),
+				}
+			})
+			.collect()
+	}
+
+	fn candle(&self, indices: &[Option<usize>], row: &[String]) -> Result<OHLCV, String> {
+		let value = |position: usize| indices[position].and_then(|index| row.get(index)).filter(|field| !field.is_empty());
+		let number = |position: usize| match value(position) {
+			Some(field) => field.parse::<f64>().map(Some).map_err(|_| format!("{:?} isn't a number for the {:?} value.", field, KNOWN_NAMES[position].0)),
+			None => Ok(None),
+		};
+		let price = |position: usize| number(position)?.ok_or_else(|| format!("The {:?} value is missing.", KNOWN_NAMES[position].0));
+
+		let t = match value(0) {
+			Some(field) => Some(parse_timestamp(field, self.timestamp_format).ok_or_else(|| format!("{:?} isn't a timestamp.", field))?),
+			None => None,
+		};
+
+		Ok(OHLCV {
+			t,
+			o: price(1)?,
+			h: price(2)?,
+			l: price(3)?,
+			c: price(4)?,
+			v: number(5)?.unwrap_or(0.),
+			bv: number(6)?,
+		})
+	}
+}
+
+/// Splits the line into its fields, which may be quoted with '"' and escape quotes by doubling them
+fn split_row(line: &str, delimiter: char) -> Vec<String> {
+	let mut fields = vec![];
+	let mut field = String::new();
+	let mut quoted = false;
+	let mut chars = line.trim_end_matches('\r').chars().peekable();
+
+	while let Some(c) = chars.next() {
+		match c {
+			'"' if quoted && chars.peek() == Some(&'"') => {
+				field.push('"');
+				chars.next();
+			}
+			'"' => quoted = !quoted,
+			c if c == delimiter && !quoted => fields.push(field.split_off(0).trim().to_string()),
+			c => field.push(c),
+		}
+	}
+
+	fields.push(field.trim().to_string());
+
+	fields
+}
+
+/// Unix timestamp, in seconds, of the text
+pub fn parse_timestamp(text: &str, format: TimestampFormat) -> Option<i64> {
+	let unix = || text.parse::<i64>().ok().or_else(|| text.parse::<f64>().ok().filter(|t| t.is_finite()).map(|t| t as i64));
+
+	match format {
+		TimestampFormat::UnixSeconds => unix(),
+		TimestampFormat::UnixMillis => unix().map(|t| t.div_euclid(1000)),
+		TimestampFormat::Iso8601 => parse_iso8601(text),
+		TimestampFormat::Auto => match unix() {
+			Some(t) if t.abs() >= 100_000_000_000 => Some(t.div_euclid(1000)),
+			Some(t) => Some(t),
+			None => parse_iso8601(text),
+		},
+	}
+}
+
+fn parse_iso8601(text: &str) -> Option<i64> {
+	if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+		return Some(time.timestamp());
+	}
+
+	for format in &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+		if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+			return Some(time.and_utc().timestamp());
+		}
+	}
+
+	NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)).map(|time| time.and_utc().timestamp())
+}
+
+#[cfg(test)]
+#[test]
+fn split_row_test() {
+	assert_eq!(split_row("1, 2.5,\"3,5\",\"say \"\"hi\"\"\"\r", ','), vec!["1", "2.5", "3,5", "say \"hi\""]);
+	assert_eq!(split_row("a;;b", ';'), vec!["a", "", "b"]);
+}
+
+#[cfg(test)]
+#[test]
+fn parse_timestamp_test() {
+	assert_eq!(parse_timestamp("1538006400", TimestampFormat::Auto), Some(1538006400));
+	assert_eq!(parse_timestamp("1538006400000", TimestampFormat::Auto), Some(1538006400));
+	assert_eq!(parse_timestamp("1538006400", TimestampFormat::UnixMillis), Some(1538006));
+	assert_eq!(parse_timestamp("2018-09-27T00:00:00Z", TimestampFormat::Auto), Some(1538006400));
+	assert_eq!(parse_timestamp("2018-09-27T02:00:00+02:00", TimestampFormat::Iso8601), Some(1538006400));
+	assert_eq!(parse_timestamp("2018-09-27 00:00:00", TimestampFormat::Auto), Some(1538006400));
+	assert_eq!(parse_timestamp("2018-09-27", TimestampFormat::Auto), Some(1538006400));
+	assert_eq!(parse_timestamp("2018-09-27", TimestampFormat::UnixSeconds), None);
+	assert_eq!(parse_timestamp("yesterday", TimestampFormat::Auto), None);
+}
+
+#[cfg(test)]
+#[test]
+fn load_test() {
+	let headed = "Date,Open,High,Low,Close,Volume\n2018-09-27,1,2,0.5,1.5,10\n\n2018-09-28,1.5,3,1,2,\n";
+	let candles = CsvLoader::new().load(headed.as_bytes()).unwrap();
+
+	assert_eq!(candles, vec![
+		OHLCV { t: Some(1538006400), o: 1., h: 2., l: 0.5, c: 1.5, v: 10., bv: None },
+		OHLCV { t: Some(1538092800), o: 1.5, h: 3., l: 1., c: 2., v: 0., bv: None },
+	]);
+
+	// Positional columns without a header
+	let bare = "1538006400000;1;2;0.5;1.5;10;4\n";
+	let candles = CsvLoader::new().delimiter(';').column(CandleField::BuyVolume, 6).load(bare.as_bytes()).unwrap();
+	assert_eq!(candles[0], OHLCV { t: Some(1538006400), o: 1., h: 2., l: 0.5, c: 1.5, v: 10., bv: Some(4.) });
+
+	let renamed = "unix,price_open,price_high,price_low,price_close,Volume BTC\n1538006400,1,2,0.5,1.5,10\n";
+	let candles = CsvLoader::new()
+		.column(CandleField::Open, "price_open")
+		.column(CandleField::High, "price_high")
+		.column(CandleField::Low, "price_low")
+		.column(CandleField::Close, "price_close")
+		.column(CandleField::Volume, "volume btc")
+		.load(renamed.as_bytes())
+		.unwrap();
+	assert_eq!(candles[0], OHLCV { t: Some(1538006400), o: 1., h: 2., l: 0.5, c: 1.5, v: 10., bv: None });
+
+	match CsvLoader::new().load("t,o,h,l,c\n0,1,2,0.5,1.5\n60,1,x,0.5,1.5\n".as_bytes()) {
+		Err(OhlcError::Parse { line: 3, .. }) => {}
+		result => panic!("expected a parse error on line 3, got {:?}", result),
+	}
+
+	match CsvLoader::new().column(CandleField::Close, "last").load(headed.as_bytes()) {
+		Err(OhlcError::Parse { line: 1, .. }) => {}
+		result => panic!("expected a parse error on the header, got {:?}", result),
+	}
+
+	// An empty optional cell in the first row doesn't make it a header
+	let missing_volume = "2018-09-27,1,2,0.5,1.5,\n2018-09-28,1.5,3,1,2,10\n";
+	let candles = CsvLoader::new().load(missing_volume.as_bytes()).unwrap();
+	assert_eq!(candles, vec![
+		OHLCV { t: Some(1538006400), o: 1., h: 2., l: 0.5, c: 1.5, v: 0., bv: None },
+		OHLCV { t: Some(1538092800), o: 1.5, h: 3., l: 1., c: 2., v: 10., bv: None },
+	]);
+
+	let prices = CsvLoader::new().load("1,2,0.5,1.5\n".as_bytes()).unwrap();
+	assert_eq!(prices[0], OHLCV { t: None, o: 1., h: 2., l: 0.5, c: 1.5, v: 0., bv: None });
+
+	assert_eq!(CsvLoader::new().load("".as_bytes()).unwrap(), vec![]);
+}
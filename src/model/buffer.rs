@@ -1,68 +1,146 @@
 pub use OHLCRenderOptions;
 
+use error::OhlcError;
+use format::NumberFormat;
+use model::labels::PriceLabel;
+use theme::Theme;
+
 use super::*;
 
-pub struct ChartBuffer {
-	/// Total width for the graph
-	width: usize,
-	/// Total height for the graph
-	height: usize,
+/// How prices are mapped onto the vertical axis of the main chart
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PriceScale {
+	Linear,
+	/// Equal ratios take up equal heights, requires all prices to be positive
+	Logarithmic,
+}
+
+/// Room between the extreme prices and the edges of the main chart
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PricePadding {
+	/// Percentage of the price range, of the ratio between the extremes on logarithmic scales
+	Percent(f64),
+	/// In currency units, on logarithmic scales the bottom is only padded while it stays positive
+	Absolute(f64),
+}
+
+impl PricePadding {
+	/// The price moved away from the range by the padding, upwards for the high end of the range
+	pub fn apply(&self, price: f64, high: f64, low: f64, upwards: bool, price_scale: PriceScale) -> f64 {
+		let sign = if upwards { 1. } else { -1. };
+
+		match (*self, price_scale) {
+			(PricePadding::Percent(percent), PriceScale::Linear) => price + sign * (high - low) * percent / 100.,
+			(PricePadding::Percent(percent), PriceScale::Logarithmic) => price * (high / low).powf(sign * percent / 100.),
+			(PricePadding::Absolute(amount), PriceScale::Logarithmic) if price - amount <= 0. && !upwards => price,
+			(PricePadding::Absolute(amount), _) => price + sign * amount,
+		}
+	}
+}
+
+pub struct ChartBuffer<'a> {
 	/// Margin for the actual graph
 	pub margin: Margin,
 	/// Maximum price the graph is able to display
 	pub max_price: f64,
 	/// Minimum price the graph is able to display
 	pub min_price: f64,
+	/// Mapping of prices onto the vertical axis
+	pub price_scale: PriceScale,
 	/// The amount of time the graph covers, in seconds
 	pub timeframe: i64,
+	/// Placement of the candles along the time axis
+	pub time_scale: TimeScale,
+	/// Formatting of prices in labels
+	pub price_format: NumberFormat,
+	/// Formatting of volumes in labels
+	pub volume_format: NumberFormat,
 	/// Default background colour, alpha channel is ignored
 	pub background: u32,
-	/// Byte buffer of the actual image
-	pub buffer: Vec<u8>,
+	/// Output backend of the actual image
+	pub surface: &'a mut dyn DrawingSurface,
+	/// Colours for extensions created without colours
+	pub theme: Theme,
+	/// Position of the extension being applied, which picks its colours from the indicator palette of the theme
+	pub palette_index: usize,
+	/// Labels of the right axis, drawn after all renderers
+	pub(crate) price_labels: Vec<PriceLabel>,
 }
 
-impl ChartBuffer {
-	pub(crate) fn new(width: usize, height: usize, margin: Margin, max_price: f64, min_price: f64, timeframe: i64, background: u32) -> ChartBuffer {
+impl<'a> ChartBuffer<'a> {
+	pub(crate) fn new(surface: &'a mut dyn DrawingSurface, margin: Margin, max_price: f64, min_price: f64, price_scale: PriceScale, time_scale: TimeScale, background: u32) -> Result<ChartBuffer<'a>, OhlcError> {
+		let timeframe = time_scale.timeframe();
+		let (width, height) = (surface.width(), surface.height());
+
 		if max_price < min_price {
-			panic!("max < min... wut?");
+			return Err(OhlcError::Layout(format!("The maximum price {} is below the minimum price {}.", max_price, min_price)));
 		}
 
+		if price_scale == PriceScale::Logarithmic && min_price <= 0. {
+			return Err(OhlcError::Layout("Logarithmic price scales need positive prices.".to_string()));
+		}
+
+		// Flat prices leave no range to map prices onto, so the price gets some room above and below
+		let (max_price, min_price) = if max_price == min_price {
+			match price_scale {
+				PriceScale::Linear => {
+					let padding = if max_price == 0. { 1. } else { max_price.abs() / 100. };
+					(max_price + padding, min_price - padding)
+				}
+				PriceScale::Logarithmic => (max_price * 1.01, min_price / 1.01),
+			}
+		} else {
+			(max_price, min_price)
+		};
+
 		if timeframe <= 0 {
-			panic!("timeframe must be > 0");
+			return Err(OhlcError::Layout("The candles must cover a timeframe longer than zero.".to_string()));
 		}
 
 		if margin.top + margin.bottom > height || margin.left + margin.right > width {
-			panic!("margins cannot be bigger than the image itself")
+			return Err(OhlcError::Layout("Margins cannot be bigger than the image itself.".to_string()));
 		}
 
-		let mut buffer = Vec::with_capacity(width * height * 3);
-
-		Self::colour_buffer(&mut buffer, width * height, background);
-
-		ChartBuffer { width, height, margin, max_price, min_price, timeframe, background: background | 0xFF, buffer }
+		Ok(ChartBuffer {
+			margin,
+			max_price,
+			min_price,
+			price_scale,
+			timeframe,
+			time_scale,
+			price_format: NumberFormat::new(),
+			volume_format: NumberFormat::new().si_suffixes(),
+			background: background | 0xFF,
+			surface,
+			theme: Theme::light(),
+			palette_index: 0,
+			price_labels: vec![],
+		})
 	}
 
 	/// Returns: (x, y)
 	pub fn data_to_coords(&self, price: f64, time: i64) -> Point {
+		let (width, height) = (self.width(), self.height());
+
 		let x = {
 			let prog = time as f64 / self.timeframe as f64;
 
 			if prog <= 0. {
 				self.margin.left
 			} else if prog >= 1. {
-				self.width - self.margin.right
+				width - self.margin.right
 			} else {
-				self.margin.left + (prog * (self.width - (self.margin.right + self.margin.left)) as f64) as usize
+				self.margin.left + (prog * (width - (self.margin.right + self.margin.left)) as f64) as usize
 			}
 		};
 
 		let y = {
-			let prog = (price - self.min_price) / (self.max_price - self.min_price);
+			let prog = self.price_progress(price);
 
 			if prog >= 1. {
 				self.margin.top
 			} else {
-				let bottom = self.height - self.margin.bottom;
+				let bottom = height - self.margin.bottom;
 
 				if prog <= 0. {
 					bottom
@@ -75,18 +153,32 @@ impl ChartBuffer {
 		(x, y)
 	}
 
+	/// How far up the price is between the minimum and maximum price, 0 being the bottom and 1 being the top
+	pub fn price_progress(&self, price: f64) -> f64 {
+		match self.price_scale {
+			PriceScale::Linear => (price - self.min_price) / (self.max_price - self.min_price),
+			PriceScale::Logarithmic => (price / self.min_price).ln() / (self.max_price / self.min_price).ln(),
+		}
+	}
+
 	pub fn create_extension_strip<F>(&mut self, height: usize, f: F) where F: Fn(&mut ExtensionStrip) {
-		// Have enough room for labels on the top, bottom and right
-		let margin = Margin { top: 40, bottom: 35, left: self.margin.left, right: self.margin.right };
+		// Have enough room for labels on the top, bottom and right, shrinking for short strips
+		let margin = Margin {
+			top: 40.min(height * 3 / 10),
+			bottom: 35.min(height * 3 / 10),
+			left: self.margin.left,
+			right: self.margin.right,
+		};
 
-		self.height += height;
 		self.margin.bottom += height;
 
-		let mut es = ExtensionStrip::new(self.width, height, self.background, self.timeframe, margin);
+		// Strips are drawn onto the rows added underneath the chart
+		let top = self.height();
+		self.surface.extend(height, self.background);
 
-		(f)(&mut es);
+		let mut es = ExtensionStrip::new(SubSurface::new(&mut *self.surface, top), self.background, self.time_scale.clone(), margin);
 
-		self.buffer.extend(es.buffer);
+		(f)(&mut es);
 	}
 
 	pub fn put(&mut self, price: f64, time: i64, rgba: u32) {
@@ -95,17 +187,17 @@ impl ChartBuffer {
 	}
 }
 
-impl Painter for ChartBuffer {
-	fn buffer<'a>(&'a mut self) -> &mut Vec<u8> {
-		&mut self.buffer
+impl<'a> Painter for ChartBuffer<'a> {
+	fn surface(&mut self) -> &mut dyn DrawingSurface {
+		&mut *self.surface
 	}
 
 	fn width(&self) -> usize {
-		self.width
+		self.surface.width()
 	}
 
 	fn height(&self) -> usize {
-		self.height
+		self.surface.height()
 	}
 
 	fn background(&self) -> u32 {
@@ -113,41 +205,47 @@ impl Painter for ChartBuffer {
 	}
 }
 
-pub struct ExtensionStrip {
-	width: usize,
-	height: usize,
+/// Raw pixels of a fully rendered chart
+pub struct RgbBuffer {
+	/// Total width of the image
+	pub width: usize,
+	/// Total height of the image, extension strips included
+	pub height: usize,
+	/// RGB(8) bytes of the image, row by row
+	pub buffer: Vec<u8>,
+}
+
+pub struct ExtensionStrip<'a> {
 	pub background: u32,
 	pub timeframe: i64,
+	pub time_scale: TimeScale,
 	pub margin: Margin,
-	pub buffer: Vec<u8>,
+	pub surface: SubSurface<'a>,
 }
 
-impl ExtensionStrip {
-	pub fn new(width: usize, height: usize, background: u32, timeframe: i64, margin: Margin) -> ExtensionStrip {
-		let mut buffer = Vec::with_capacity(width * height * 3);
-
-		Self::colour_buffer(&mut buffer, width * height, background);
-
+impl<'a> ExtensionStrip<'a> {
+	pub fn new(surface: SubSurface<'a>, background: u32, time_scale: TimeScale, margin: Margin) -> ExtensionStrip<'a> {
 		ExtensionStrip {
-			width,
-			height,
 			background,
-			timeframe,
+			timeframe: time_scale.timeframe(),
+			time_scale,
 			margin,
-			buffer,
+			surface,
 		}
 	}
 
 	pub fn data_to_coords(&self, up_progress: f64, time: i64) -> Point {
+		let (width, height) = (self.width(), self.height());
+
 		let x = {
 			let prog = time as f64 / self.timeframe as f64;
 
 			if prog <= 0. {
 				self.margin.left
 			} else if prog >= 1. {
-				self.width - self.margin.right
+				width - self.margin.right
 			} else {
-				self.margin.left + (prog * (self.width - (self.margin.right + self.margin.left)) as f64) as usize
+				self.margin.left + (prog * (width - (self.margin.right + self.margin.left)) as f64) as usize
 			}
 		};
 
@@ -155,7 +253,7 @@ impl ExtensionStrip {
 			if up_progress >= 1. {
 				self.margin.top
 			} else {
-				let bottom = self.height - self.margin.bottom;
+				let bottom = height - self.margin.bottom;
 
 				if up_progress <= 0. {
 					bottom
@@ -169,20 +267,37 @@ impl ExtensionStrip {
 	}
 }
 
-impl Painter for ExtensionStrip {
-	fn buffer<'a>(&'a mut self) -> &mut Vec<u8> {
-		&mut self.buffer
+impl<'a> Painter for ExtensionStrip<'a> {
+	fn surface(&mut self) -> &mut dyn DrawingSurface {
+		&mut self.surface
 	}
 
 	fn width(&self) -> usize {
-		self.width
+		self.surface.width()
 	}
 
 	fn height(&self) -> usize {
-		self.height
+		self.surface.height()
 	}
 
 	fn background(&self) -> u32 {
 		self.background
 	}
 }
+
+#[cfg(test)]
+#[test]
+fn price_padding_test() {
+	use self::PricePadding::*;
+	use self::PriceScale::*;
+
+	assert_eq!(Percent(10.).apply(200., 200., 100., true, Linear), 210.);
+	assert_eq!(Percent(10.).apply(100., 200., 100., false, Linear), 90.);
+	assert_eq!(Absolute(5.).apply(100., 200., 100., false, Linear), 95.);
+
+	// 10% of the ratio of 4 between the extremes
+	assert!((Percent(10.).apply(400., 400., 100., true, Logarithmic) - 400. * 4_f64.powf(0.1)).abs() < 1e-9);
+	assert!((Percent(10.).apply(100., 400., 100., false, Logarithmic) - 100. / 4_f64.powf(0.1)).abs() < 1e-9);
+	// Would go below zero
+	assert_eq!(Absolute(150.).apply(100., 400., 100., false, Logarithmic), 100.);
+}
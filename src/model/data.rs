@@ -1,6 +1,10 @@
 use std::hash::{Hash, Hasher};
 
+use serde::de::{self, Deserialize, Deserializer};
+
 use Candle;
+use loader::{parse_timestamp, TimestampFormat};
+use utils::SetAggregate;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct OHLC {
@@ -8,6 +12,9 @@ pub struct OHLC {
 	pub h: f64,
 	pub l: f64,
 	pub c: f64,
+	/// Unix timestamp, in seconds, of when the candle opens
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub t: Option<i64>,
 }
 
 impl Hash for OHLC {
@@ -49,6 +56,27 @@ impl Candle for OHLC {
 	fn total_volume(&self) -> f64 {
 		0.0
 	}
+
+	#[inline]
+	fn timestamp(&self) -> Option<i64> {
+		self.t
+	}
+
+	fn with_prices(&self, open: f64, high: f64, low: f64, close: f64) -> Option<OHLC> {
+		Some(OHLC { o: open, h: high, l: low, c: close, t: self.t })
+	}
+}
+
+impl From<SetAggregate> for OHLC {
+	fn from(set: SetAggregate) -> OHLC {
+		OHLC { o: set.o, h: set.h, l: set.l, c: set.c, t: set.start }
+	}
+}
+
+impl Default for OHLC {
+	fn default() -> OHLC {
+		OHLC::new()
+	}
 }
 
 impl OHLC {
@@ -58,10 +86,133 @@ impl OHLC {
 			h: 0.0,
 			l: 0.0,
 			c: 0.0,
+			t: None,
 		}
 	}
 
 	pub fn range(&self) -> f64 {
 		(self.h - self.l).abs()
 	}
-}
\ No newline at end of file
+}
+
+/// Candle with volumes, its fields also read from the names exchanges commonly use, e.g. `open` or `volume`,
+/// and numbers may be given as strings.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct OHLCV {
+	/// Unix timestamp, in seconds, of when the candle opens. Reads milliseconds and ISO 8601 dates too, the same way
+	/// `CsvLoader` does.
+	#[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "optional_timestamp")]
+	#[serde(alias = "time", alias = "timestamp", alias = "ts", alias = "start", alias = "date")]
+	#[serde(alias = "open_time", alias = "openTime")]
+	pub t: Option<i64>,
+	#[serde(deserialize_with = "number", alias = "open", alias = "Open")]
+	pub o: f64,
+	#[serde(deserialize_with = "number", alias = "high", alias = "High")]
+	pub h: f64,
+	#[serde(deserialize_with = "number", alias = "low", alias = "Low")]
+	pub l: f64,
+	#[serde(deserialize_with = "number", alias = "close", alias = "Close")]
+	pub c: f64,
+	/// Total volume
+	#[serde(default, deserialize_with = "number")]
+	#[serde(alias = "volume", alias = "Volume", alias = "vol", alias = "total_volume", alias = "baseVolume")]
+	pub v: f64,
+	/// Volume of trades where the buyer was the aggressor, candles without it only show their total volume
+	#[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "optional_number")]
+	#[serde(alias = "buy_volume", alias = "taker_buy_volume", alias = "takerBuyBaseVolume")]
+	pub bv: Option<f64>,
+}
+
+impl Candle for OHLCV {
+	#[inline]
+	fn open(&self) -> f64 {
+		self.o
+	}
+
+	#[inline]
+	fn high(&self) -> f64 {
+		self.h
+	}
+
+	#[inline]
+	fn low(&self) -> f64 {
+		self.l
+	}
+
+	#[inline]
+	fn close(&self) -> f64 {
+		self.c
+	}
+
+	#[inline]
+	fn buy_volume(&self) -> Option<f64> {
+		self.bv
+	}
+
+	#[inline]
+	fn total_volume(&self) -> f64 {
+		self.v
+	}
+
+	#[inline]
+	fn timestamp(&self) -> Option<i64> {
+		self.t
+	}
+
+	fn with_prices(&self, open: f64, high: f64, low: f64, close: f64) -> Option<OHLCV> {
+		Some(OHLCV { o: open, h: high, l: low, c: close, ..*self })
+	}
+}
+
+impl From<SetAggregate> for OHLCV {
+	fn from(set: SetAggregate) -> OHLCV {
+		OHLCV { t: set.start, o: set.o, h: set.h, l: set.l, c: set.c, v: set.v, bv: set.bv }
+	}
+}
+
+impl From<OHLC> for OHLCV {
+	fn from(ohlc: OHLC) -> OHLCV {
+		OHLCV { t: ohlc.t, o: ohlc.o, h: ohlc.h, l: ohlc.l, c: ohlc.c, v: 0., bv: None }
+	}
+}
+
+/// Numbers that exchanges send either as numbers or as strings to keep their precision
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Number {
+	Integer(i64),
+	Float(f64),
+	Text(String),
+}
+
+impl Number {
+	fn float<E: de::Error>(self) -> Result<f64, E> {
+		match self {
+			Number::Integer(value) => Ok(value as f64),
+			Number::Float(value) => Ok(value),
+			Number::Text(text) => text.trim().parse().map_err(|_| E::custom(format!("invalid number {:?}", text))),
+		}
+	}
+
+	fn timestamp<E: de::Error>(self) -> Result<i64, E> {
+		let text = match self {
+			Number::Integer(value) => value.to_string(),
+			Number::Float(value) => value.to_string(),
+			Number::Text(text) => text,
+		};
+
+		parse_timestamp(text.trim(), TimestampFormat::Auto).ok_or_else(|| E::custom(format!("invalid timestamp {:?}", text)))
+	}
+}
+
+fn number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+	Number::deserialize(deserializer)?.float()
+}
+
+fn optional_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+	Option::<Number>::deserialize(deserializer)?.map(Number::float).transpose()
+}
+
+fn optional_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+	Option::<Number>::deserialize(deserializer)?.map(Number::timestamp).transpose()
+}
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use ab_glyph::{Font as _, FontArc, GlyphId, PxScale, ScaleFont};
+
+use error::OhlcError;
+use fonts::ASCII_TABLE;
+use model::painting::Point;
+
+/// DejaVu Sans Mono, see fonts/LICENSE-DejaVu
+static EMBEDDED_FONT: &[u8] = include_bytes!("../../fonts/DejaVuSansMono.ttf");
+
+/// Size of the glyphs of the built-in bitmap font
+pub const BITMAP_GLYPH_WIDTH: usize = 10;
+pub const BITMAP_GLYPH_HEIGHT: usize = 17;
+
+/// Coverage of a glyph rasterised at one size
+struct GlyphBitmap {
+	/// Offset of the top left corner from the pen position on the baseline
+	left: i32,
+	top: i32,
+	width: usize,
+	coverage: Vec<f32>,
+}
+
+/// Number of rasterised glyphs a face keeps before it starts over, as every distinct size adds to the cache
+const MAX_CACHED_GLYPHS: usize = 4096;
+
+/// Rasterised glyphs by glyph and line height (as bits of the f32)
+type GlyphCache = HashMap<(GlyphId, u32), Arc<GlyphBitmap>>;
+
+/// A TrueType/OpenType font with the glyphs it has rasterised so far
+#[derive(Clone)]
+struct Face {
+	font: FontArc,
+	cache: Arc<Mutex<GlyphCache>>,
+}
+
+impl Face {
+	fn new(font: FontArc) -> Face {
+		Face { font, cache: Arc::new(Mutex::new(HashMap::new())) }
+	}
+
+	fn glyph(&self, id: GlyphId, scale: PxScale) -> Arc<GlyphBitmap> {
+		let key = (id, scale.y.to_bits());
+
+		if let Some(glyph) = self.cache.lock().unwrap().get(&key) {
+			return glyph.clone();
+		}
+
+		let glyph = match self.font.outline_glyph(id.with_scale(scale)) {
+			Some(outlined) => {
+				let bounds = outlined.px_bounds();
+				let width = bounds.width() as usize;
+				let mut coverage = vec![0.; width * bounds.height() as usize];
+
+				outlined.draw(|x, y, c| coverage[x as usize + y as usize * width] = c);
+
+				GlyphBitmap { left: bounds.min.x as i32, top: bounds.min.y as i32, width, coverage }
+			}
+			// Whitespace has no outline
+			None => GlyphBitmap { left: 0, top: 0, width: 0, coverage: vec![] },
+		};
+
+		let glyph = Arc::new(glyph);
+		let mut cache = self.cache.lock().unwrap();
+		if cache.len() >= MAX_CACHED_GLYPHS {
+			cache.clear();
+		}
+		cache.insert(key, glyph.clone());
+
+		glyph
+	}
+}
+
+/// Typeface and size of the text on a chart, either the built-in bitmap font or TrueType/OpenType fonts
+#[derive(Clone)]
+pub struct Font {
+	/// Fonts in order of preference for each character, the bitmap font is used if there are none
+	faces: Vec<Face>,
+	/// Font family written into SVG output
+	family: String,
+	/// Height of a line in pixels
+	size: f32,
+}
+
+impl Default for Font {
+	fn default() -> Font {
+		Font::bitmap()
+	}
+}
+
+impl fmt::Debug for Font {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_bitmap() {
+			write!(f, "Font(bitmap)")
+		} else {
+			write!(f, "Font({}, {}px, {} faces)", self.family, self.size, self.faces.len())
+		}
+	}
+}
+
+impl Font {
+	/// The built-in 10x17 font, only covers printable ASCII and doesn't scale
+	pub fn bitmap() -> Font {
+		Font { faces: vec![], family: "Consolas, monospace".to_string(), size: BITMAP_GLYPH_HEIGHT as f32 }
+	}
+
+	/// The embedded DejaVu Sans Mono, with lines the height specified in pixels
+	pub fn embedded(size: f32) -> Font {
+		let font = FontArc::try_from_slice(EMBEDDED_FONT).expect("embedded font is valid");
+
+		Font { faces: vec![Face::new(font)], family: "DejaVu Sans Mono, monospace".to_string(), size }
+	}
+
+	/// Loads a TrueType/OpenType font, with lines the height specified in pixels
+	pub fn from_bytes(bytes: Vec<u8>, size: f32) -> Result<Font, OhlcError> {
+		let font = parse_face(bytes)?;
+
+		Ok(Font { faces: vec![Face::new(font)], family: "sans-serif".to_string(), size })
+	}
+
+	/// Adds a font to draw the characters that the previous fonts lack, e.g. CJK characters
+	pub fn fallback(mut self, bytes: Vec<u8>) -> Result<Font, OhlcError> {
+		if self.is_bitmap() {
+			return Err(OhlcError::Font("The bitmap font can't have fallbacks".to_string()));
+		}
+
+		let font = parse_face(bytes)?;
+		self.faces.push(Face::new(font));
+
+		Ok(self)
+	}
+
+	/// Sets the font family SVG viewers look for, as they draw the text with their own fonts
+	pub fn family(mut self, family: &str) -> Font {
+		self.family = family.to_string();
+
+		self
+	}
+
+	/// The same font at another size, the rasterised glyphs are shared. The bitmap font doesn't scale.
+	pub fn sized(&self, size: f32) -> Font {
+		let mut font = self.clone();
+		if !font.is_bitmap() {
+			font.size = size;
+		}
+
+		font
+	}
+
+	pub fn is_bitmap(&self) -> bool {
+		self.faces.is_empty()
+	}
+
+	pub fn size(&self) -> f32 {
+		self.size
+	}
+
+	pub fn family_name(&self) -> &str {
+		&self.family
+	}
+
+	/// Distance between the tops of consecutive lines, in pixels
+	pub fn line_height(&self) -> usize {
+		match self.faces.first() {
+			Some(face) => {
+				let font = face.font.as_scaled(self.size);
+				(font.ascent() - font.descent() + font.line_gap()).ceil() as usize
+			}
+			None => BITMAP_GLYPH_HEIGHT,
+		}
+	}
+
+	/// Distance from the top of a line to its baseline, in pixels
+	pub fn ascent(&self) -> usize {
+		match self.faces.first() {
+			Some(face) => face.font.as_scaled(self.size).ascent().round() as usize,
+			None => 13,
+		}
+	}
+
+	/// Size of the em square in pixels, which is the font size SVG viewers expect
+	pub fn em_size(&self) -> f32 {
+		match self.faces.first() {
+			Some(face) => match face.font.units_per_em() {
+				Some(units_per_em) => self.size * units_per_em / face.font.height_unscaled(),
+				None => self.size,
+			},
+			None => BITMAP_GLYPH_HEIGHT as f32,
+		}
+	}
+
+	/// Width of a line of text (without line breaks) in pixels
+	pub fn line_width(&self, line: &str) -> usize {
+		let mut width = 0.;
+		self.layout_line(line, |_, _, pen_x, advance| width = pen_x + advance);
+
+		width.ceil() as usize
+	}
+
+	/// Goes through the characters of a line with the face and glyph that draw them (none for the bitmap font),
+	/// their pen position and advance
+	fn layout_line<F: FnMut(char, Option<(&Face, GlyphId)>, f32, f32)>(&self, line: &str, mut glyph: F) {
+		if self.is_bitmap() {
+			for (i, c) in line.chars().enumerate() {
+				glyph(c, None, (i * BITMAP_GLYPH_WIDTH) as f32, BITMAP_GLYPH_WIDTH as f32);
+			}
+			return;
+		}
+
+		let mut pen_x = 0.;
+		let mut previous: Option<(usize, GlyphId)> = None;
+
+		for c in line.chars() {
+			// The first font with the glyph draws it, or the first font draws its "missing glyph" box
+			let (index, id) = self.faces.iter()
+				.map(|face| face.font.glyph_id(c))
+				.enumerate()
+				.find(|&(_, id)| id.0 != 0)
+				.unwrap_or((0, GlyphId(0)));
+
+			let face = &self.faces[index];
+			let font = face.font.as_scaled(self.size);
+
+			if let Some((previous_index, previous_id)) = previous {
+				if previous_index == index {
+					pen_x += font.kern(previous_id, id);
+				}
+			}
+
+			let advance = font.h_advance(id);
+			glyph(c, Some((face, id)), pen_x, advance);
+
+			pen_x += advance;
+			previous = Some((index, id));
+		}
+	}
+
+	/// Rasterises the text with its top left corner specified, plotting the coverage (0 to 1) of every pixel it touches
+	pub(crate) fn rasterise<F: FnMut(usize, usize, f32)>(&self, topleft: Point, text: &str, mut plot: F) {
+		let line_height = self.line_height();
+		let ascent = self.ascent() as f32;
+		let scale = PxScale::from(self.size);
+
+		for (row, line) in text.split('\n').enumerate() {
+			let top = topleft.1 + row * line_height;
+
+			self.layout_line(line, |c, glyph, pen_x, _| {
+				match glyph {
+					Some((face, id)) => {
+						let bitmap = face.glyph(id, scale);
+						let origin_x = (topleft.0 as f32 + pen_x).round() as i64 + bitmap.left as i64;
+						let origin_y = (top as f32 + ascent) as i64 + bitmap.top as i64;
+
+						for (i, &coverage) in bitmap.coverage.iter().enumerate() {
+							let x = origin_x + (i % bitmap.width) as i64;
+							let y = origin_y + (i / bitmap.width) as i64;
+
+							if coverage > 0. && x >= 0 && y >= 0 {
+								plot(x as usize, y as usize, coverage);
+							}
+						}
+					}
+					None => {
+						// Characters outside the printable ASCII range of the bitmap font are left blank
+						let face = ASCII_TABLE.get(c as usize).unwrap_or(&ASCII_TABLE[0x20]);
+						let left = topleft.0 + pen_x as usize;
+
+						for delta_x in 0..BITMAP_GLYPH_WIDTH {
+							for delta_y in 0..BITMAP_GLYPH_HEIGHT {
+								let coverage = face[delta_x + delta_y * BITMAP_GLYPH_WIDTH];
+
+								if coverage > 0 {
+									plot(left + delta_x, top + delta_y, coverage as f32 / 255.);
+								}
+							}
+						}
+					}
+				}
+			});
+		}
+	}
+}
+
+fn parse_face(bytes: Vec<u8>) -> Result<FontArc, OhlcError> {
+	FontArc::try_from_vec(bytes).map_err(|_| OhlcError::Font("Font data is not a valid TrueType/OpenType font".to_string()))
+}
+
+#[cfg(test)]
+#[test]
+fn bitmap_metrics_test() {
+	let font = Font::bitmap();
+
+	assert_eq!(font.line_width("7630.8"), 60);
+	assert_eq!(font.line_width("€"), 10);
+	assert_eq!(font.line_height(), 17);
+	assert_eq!(font.sized(30.).line_height(), 17);
+}
+
+#[cfg(test)]
+#[test]
+fn embedded_metrics_test() {
+	let font = Font::embedded(16.);
+
+	// Monospace, so every glyph advances the same
+	assert_eq!(font.line_width("0000"), font.line_width("€€€€"));
+	assert!(font.line_width("7630.8") < font.sized(32.).line_width("7630.8"));
+	assert!(font.line_height() >= 16);
+	assert!(font.ascent() < font.line_height());
+}
+
+#[cfg(test)]
+#[test]
+fn rasterise_test() {
+	let font = Font::embedded(16.);
+	let mut pixels = vec![];
+	font.rasterise((100, 50), "€\n€", |x, y, coverage| pixels.push((x, y, coverage)));
+
+	assert!(!pixels.is_empty());
+	assert!(pixels.iter().all(|&(x, y, coverage)| x >= 100 && y >= 50 && y < 50 + 2 * font.line_height() && coverage > 0.));
+	assert!(pixels.iter().any(|&(_, y, _)| y >= 50 + font.line_height()));
+}
+
+#[cfg(test)]
+#[test]
+fn glyph_cache_bound_test() {
+	let font = Font::embedded(16.);
+	let face = &font.faces[0];
+	let id = face.font.glyph_id('7');
+
+	for i in 0..MAX_CACHED_GLYPHS + 10 {
+		face.glyph(id, PxScale::from(1. + i as f32 / 1000.));
+	}
+
+	assert!(face.cache.lock().unwrap().len() <= MAX_CACHED_GLYPHS);
+	assert!(Arc::ptr_eq(&face.glyph(id, PxScale::from(1.)), &face.glyph(id, PxScale::from(1.))));
+}
+
+#[cfg(test)]
+#[test]
+fn invalid_font_test() {
+	assert!(matches!(Font::from_bytes(vec![0; 64], 16.), Err(OhlcError::Font(_))));
+	assert!(matches!(Font::bitmap().fallback(EMBEDDED_FONT.to_vec()), Err(OhlcError::Font(_))));
+	assert!(matches!(Font::embedded(16.).fallback(vec![]), Err(OhlcError::Font(_))));
+}
@@ -0,0 +1,128 @@
+use std::mem;
+
+use model::buffer::ChartBuffer;
+use model::painting::{Anchor, Painter};
+
+/// Importance of a label on the price axis, more important labels keep their place when labels overlap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LabelPriority {
+	/// Hidden when it overlaps any other label
+	Grid,
+	/// Values of indicators, e.g. the last value of a moving average
+	Indicator,
+	/// Highest and lowest prices
+	Extreme,
+	/// The current price
+	Current,
+}
+
+/// A label on the right side of the main chart, drawn once every renderer has added theirs
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceLabel {
+	pub price: f64,
+	pub text: String,
+	pub colour: u32,
+	/// Whether a box is drawn around the text
+	pub outlined: bool,
+	pub priority: LabelPriority,
+}
+
+impl<'a> ChartBuffer<'a> {
+	/// Queues a label next to the price on the right axis, overlapping labels are moved apart or hidden when drawn
+	pub fn price_label(&mut self, price: f64, text: &str, colour: u32, outlined: bool, priority: LabelPriority) {
+		self.price_labels.push(PriceLabel { price, text: text.to_string(), colour, outlined, priority });
+	}
+
+	/// Lays out and draws the queued price labels
+	pub(crate) fn draw_price_labels(&mut self) {
+		let labels = mem::take(&mut self.price_labels);
+
+		let positions = labels.iter()
+			.map(|label| {
+				let (x, y) = self.data_to_coords(label.price, self.timeframe);
+				let height = self.measure_text(&label.text).1 + if label.outlined { 2 } else { 0 };
+
+				(x, (y as f64, height as f64, label.priority))
+			})
+			.collect::<Vec<_>>();
+
+		// Labels may stick out below the plotting area by up to a line
+		let bottom = self.data_to_coords(self.min_price, 0).1 + self.line_height();
+		let centres = layout(&positions.iter().map(|&(_, label)| label).collect::<Vec<_>>(), (0., bottom as f64));
+
+		for ((label, &(x, _)), centre) in labels.iter().zip(&positions).zip(centres) {
+			let centre = match centre {
+				Some(centre) => centre as usize,
+				None => continue,
+			};
+
+			if label.outlined {
+				self.text_with_outline_anchored((x + 3, centre), &label.text, label.colour, Anchor::MIDDLE_LEFT);
+			} else {
+				self.text_anchored((x + 4, centre), &label.text, label.colour, Anchor::MIDDLE_LEFT);
+			}
+		}
+	}
+}
+
+/// Vertical centres for labels given as (desired centre, height, priority), none for hidden labels.
+/// Labels are placed by descending priority as close as possible to where they want to be without overlapping.
+pub fn layout(labels: &[(f64, f64, LabelPriority)], bounds: (f64, f64)) -> Vec<Option<f64>> {
+	let mut order = (0..labels.len()).collect::<Vec<_>>();
+	order.sort_by(|&a, &b| labels[b].2.cmp(&labels[a].2));
+
+	let mut centres = vec![None; labels.len()];
+	// (top, bottom) of the labels placed so far
+	let mut placed: Vec<(f64, f64)> = vec![];
+
+	for i in order {
+		let (desired, height, priority) = labels[i];
+		let half = height / 2.;
+
+		let fits = |centre: f64| {
+			centre - half >= bounds.0 - 1e-9 && centre + half <= bounds.1 + 1e-9 &&
+				placed.iter().all(|&(top, bottom)| centre + half <= top + 1e-9 || centre - half >= bottom - 1e-9)
+		};
+
+		let centre = if fits(desired) {
+			Some(desired)
+		} else if priority == LabelPriority::Grid {
+			None
+		} else {
+			// Right above or below one of the placed labels, or against the edges
+			let candidates = placed.iter()
+				.flat_map(|&(top, bottom)| vec![top - half, bottom + half])
+				.chain(vec![bounds.0 + half, bounds.1 - half]);
+
+			candidates.filter(|&centre| fits(centre))
+				.min_by(|a, b| (a - desired).abs().partial_cmp(&(b - desired).abs()).unwrap())
+				.or(Some(desired))
+		};
+
+		if let Some(centre) = centre {
+			placed.push((centre - half, centre + half));
+			centres[i] = Some(centre);
+		}
+	}
+
+	centres
+}
+
+#[cfg(test)]
+#[test]
+fn layout_test() {
+	use self::LabelPriority::*;
+
+	// Apart from each other
+	assert_eq!(layout(&[(100., 19., Current), (200., 17., Grid)], (0., 650.)), vec![Some(100.), Some(200.)]);
+
+	// Grid labels make way
+	assert_eq!(layout(&[(200., 17., Grid), (205., 19., Current)], (0., 650.)), vec![None, Some(205.)]);
+
+	// Less important labels are nudged away from the more important ones
+	assert_eq!(layout(&[(105., 19., Extreme), (100., 19., Current)], (0., 650.)), vec![Some(119.), Some(100.)]);
+	assert_eq!(layout(&[(95., 19., Extreme), (100., 19., Current)], (0., 650.)), vec![Some(81.), Some(100.)]);
+
+	// Labels stay within bounds
+	assert_eq!(layout(&[(5., 19., Current), (3., 19., Extreme)], (0., 650.)), vec![Some(9.5), Some(28.5)]);
+}
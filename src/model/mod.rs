@@ -1,14 +1,24 @@
 pub use buffer::*;
 pub use data::Candle;
 pub use painting::*;
+pub use self::font::Font;
+pub use self::labels::{LabelPriority, PriceLabel};
+pub use theme::Theme;
+pub use self::surface::{DrawingSurface, RasterSurface, SubSurface, VectorSurface};
+pub use self::time_scale::{TimeAxis, TimeScale};
 
 pub use self::rex::RendererExtension;
 
 pub mod buffer;
+pub mod font;
+pub mod labels;
 pub mod painting;
 pub mod rex;
+pub mod surface;
+pub mod time_scale;
 pub mod data;
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub struct Margin {
 	pub top: usize,
 	pub bottom: usize,
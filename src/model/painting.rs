@@ -1,104 +1,173 @@
-use std::mem;
-
-use fonts::ASCII_TABLE;
 pub use OHLCRenderOptions;
 
+use model::surface::DrawingSurface;
+
 pub type Point = (usize, usize);
 
-pub trait Painter {
-	fn buffer<'a>(&'a mut self) -> &'a mut Vec<u8>;
+/// Which side of the text is placed on the position it is drawn at
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum HorizontalAlign {
+	Left,
+	Centre,
+	Right,
+}
 
-	fn width(&self) -> usize;
+/// Which line of the text is placed on the position it is drawn at
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum VerticalAlign {
+	Top,
+	Middle,
+	/// The baseline of the first line
+	Baseline,
+	Bottom,
+}
 
-	fn height(&self) -> usize;
+/// Point of the text (or of the box around it) that is placed on the position it is drawn at
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Anchor {
+	pub horizontal: HorizontalAlign,
+	pub vertical: VerticalAlign,
+}
 
-	fn background(&self) -> u32;
+impl Anchor {
+	pub const TOP_LEFT: Anchor = Anchor { horizontal: HorizontalAlign::Left, vertical: VerticalAlign::Top };
+	pub const TOP_CENTRE: Anchor = Anchor { horizontal: HorizontalAlign::Centre, vertical: VerticalAlign::Top };
+	pub const MIDDLE_LEFT: Anchor = Anchor { horizontal: HorizontalAlign::Left, vertical: VerticalAlign::Middle };
+	pub const MIDDLE_RIGHT: Anchor = Anchor { horizontal: HorizontalAlign::Right, vertical: VerticalAlign::Middle };
+	pub const CENTRE: Anchor = Anchor { horizontal: HorizontalAlign::Centre, vertical: VerticalAlign::Middle };
 
-	/// Render a rectangle by the diagonally opposite points and colour
-	fn rect_point(&mut self, p1: Point, p2: Point, rgba: u32) {
-		self.rect(p1.0, p1.1, p2.0, p2.1, rgba);
+	pub fn new(horizontal: HorizontalAlign, vertical: VerticalAlign) -> Anchor {
+		Anchor { horizontal, vertical }
 	}
+}
 
-	/// Render a rectangle by the min/max x and y points and colour
-	fn rect(&mut self, mut x1: usize, mut y1: usize, mut x2: usize, mut y2: usize, rgba: u32) {
-		if x1 > x2 {
-			mem::swap(&mut x1, &mut x2);
-		}
-		if y1 > y2 {
-			mem::swap(&mut y1, &mut y2);
-		}
+/// Pattern of the drawn and skipped parts along a line
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Dash {
+	Solid,
+	/// Dashes of the first length with gaps of the second length, in pixels
+	Dashed(usize, usize),
+	/// Round dots as wide as the line, two line widths apart
+	Dotted,
+}
 
-		for x in x1..(x2 + 1) {
-			for y in y1..(y2 + 1) {
-				self.colour(x, y, rgba);
-			}
-		}
+/// Shape of the ends of a line, or of each dash of it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LineCap {
+	/// Ends exactly at the end points
+	Butt,
+	/// Extends a half circle past the end points
+	Round,
+	/// Extends half the line width past the end points
+	Square,
+}
+
+/// How lines are drawn, mutate through the methods
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Stroke {
+	/// Width of the line in pixels
+	pub width: usize,
+	pub dash: Dash,
+	pub cap: LineCap,
+}
+
+impl Default for Stroke {
+	fn default() -> Stroke {
+		Stroke::new()
 	}
+}
 
-	/// Draw a line between two points
-	fn line(&mut self, mut p1: Point, mut p2: Point, rgba: u32) {
-		let mut pixels = vec![];
+impl Stroke {
+	/// Creates a solid stroke one pixel wide, which draws the same as a plain line
+	pub fn new() -> Stroke {
+		Stroke { width: 1, dash: Dash::Solid, cap: LineCap::Butt }
+	}
 
-		if p1.0 > p2.0 {
-			mem::swap(&mut p1, &mut p2);
-		}
+	pub fn width(mut self, width: usize) -> Stroke {
+		self.width = width.max(1);
 
-		let adjacent = (p2.0 as i64 - p1.0 as i64) as f64;
-		let opposite = (p2.1 as i64 - p1.1 as i64) as f64;
-		let tan = opposite / adjacent;
+		self
+	}
 
-		for x in p1.0..p2.0 {
-			let y = (p1.1 as f64 + if (x - p1.0) != 0 { tan * (x - p1.0) as f64 } else { 0. }) as usize;
-			pixels.push((x, y));
-		}
+	pub fn dashed(mut self, dash_length: usize, gap_length: usize) -> Stroke {
+		self.dash = Dash::Dashed(dash_length, gap_length);
 
-		if p1.1 > p2.1 {
-			mem::swap(&mut p1, &mut p2);
-		}
+		self
+	}
 
-		for y in p1.1..p2.1 {
-			let x = (p1.0 as f64 + if tan != 0. { (y - p1.1) as f64 / tan } else { 0. }) as usize;
-			pixels.push((x, y));
-		}
+	pub fn dotted(mut self) -> Stroke {
+		self.dash = Dash::Dotted;
 
-		pixels.dedup_by(|a, b| a == b);
+		self
+	}
 
-		for (x, y) in pixels {
-			self.colour(x, y, rgba);
-		}
+	pub fn cap(mut self, cap: LineCap) -> Stroke {
+		self.cap = cap;
+
+		self
 	}
 
-	/// Colour a pixel by x and y coordinates
-	fn colour(&mut self, x: usize, y: usize, rgba: u32) {
-		let height = self.height();
-		let width = self.width();
-		let buffer = self.buffer();
+	/// Whether this stroke draws the same as a plain line
+	pub fn is_plain(&self) -> bool {
+		self.width <= 1 && self.dash == Dash::Solid
+	}
+
+	/// Lengths of the drawn and skipped parts of the line, none if it is solid
+	pub fn pattern(&self) -> Option<(f64, f64)> {
+		match self.dash {
+			Dash::Solid => None,
+			Dash::Dashed(dash_length, gap_length) => Some((dash_length as f64, gap_length as f64)),
+			Dash::Dotted => Some((0., 2. * self.width as f64)),
+		}
+	}
 
-		if x >= width || y >= height {
-			return;
+	/// Shape of the ends, dots are always round
+	pub fn effective_cap(&self) -> LineCap {
+		match self.dash {
+			Dash::Dotted => LineCap::Round,
+			_ => self.cap,
 		}
+	}
+}
+
+pub trait Painter {
+	/// The backend that all drawing operations are delegated to
+	fn surface(&mut self) -> &mut dyn DrawingSurface;
 
-		// Weird casts because I wanna strip the first 24 bits
-		let alpha = (rgba as u8) as f64 / 255.;
+	fn width(&self) -> usize;
 
-		for j in 0..3 {
-			let i = (x + y * width) * 3 + j;
+	fn height(&self) -> usize;
 
-			let applied_colour = {
-				let colour = (rgba >> (24 - 8 * j)) as u8;
-				if alpha >= 0.96 { // Lazy if opacity is >= 96%
-					colour
-				} else if alpha <= 0.04 { // Lazy if opacity is <= 4%
-					continue;
-				} else {
-					let bgc = buffer[i];
+	fn background(&self) -> u32;
 
-					(((alpha * colour as f64) + ((1. - alpha) * bgc as f64)).round()) as u8
-				}
-			};
+	/// Render a rectangle by the diagonally opposite points and colour
+	fn rect_point(&mut self, p1: Point, p2: Point, rgba: u32) {
+		self.rect(p1.0, p1.1, p2.0, p2.1, rgba);
+	}
 
-			buffer[i] = applied_colour;
-		}
+	/// Render a rectangle by the min/max x and y points and colour
+	fn rect(&mut self, x1: usize, y1: usize, x2: usize, y2: usize, rgba: u32) {
+		self.surface().rect(x1, y1, x2, y2, rgba);
+	}
+
+	/// Draw a line between two points
+	fn line(&mut self, p1: Point, p2: Point, rgba: u32) {
+		self.surface().line(p1, p2, rgba);
+	}
+
+	/// Draw a line between two points with the stroke style specified
+	fn stroke_line(&mut self, p1: Point, p2: Point, rgba: u32, stroke: &Stroke) {
+		self.polyline(&[p1, p2], rgba, stroke);
+	}
+
+	/// Draw connected lines through the points, dashes continue across the corners
+	fn polyline(&mut self, points: &[Point], rgba: u32, stroke: &Stroke) {
+		self.surface().polyline(points, rgba, stroke);
+	}
+
+	/// Colour a pixel by x and y coordinates
+	fn colour(&mut self, x: usize, y: usize, rgba: u32) {
+		self.surface().colour(x, y, rgba);
 	}
 
 	/// Colour a pixel located at point
@@ -107,70 +176,101 @@ pub trait Painter {
 	}
 
 	/// Paint some text in the colour provided, starting in the top left corner specified
-	fn text(&mut self, mut topleft: Point, text: &str, rgba: u32) {
-		let bytes = text.as_bytes();
-		for i in 0..bytes.len() {
-			let byte = bytes[i];
-
-			if byte == b'\n' {
-				topleft.1 += 17;
-				continue;
-			}
-
-			let table_idx = if byte > 127 { 0x20 } else { byte } as usize;
-
-			let font_face = ASCII_TABLE[table_idx];
-			for delta_x in 0..10 {
-				for delta_y in 0..17 {
-					let a = (((rgba as u8) as f64 / 255.) * font_face[delta_x + delta_y * 10] as f64) as u32;
-					// let a = font_face[delta_x + delta_y * 10] as u32;
-					self.colour(10 * i + topleft.0 + delta_x, topleft.1 + delta_y, ((rgba >> 8) << 8) + a);
-				}
-			}
-		}
+	fn text(&mut self, topleft: Point, text: &str, rgba: u32) {
+		self.surface().text(topleft, text, rgba);
 	}
 
-	/// Draw text according to specifications and a box around it as well (give 1 pix of both x and y margin). Supports a single line only.
+	/// Distance between the tops of consecutive lines of text
+	fn line_height(&mut self) -> usize {
+		self.surface().font().line_height()
+	}
+
+	/// Width of the widest line and height of all lines of the text, in pixels
+	fn measure_text(&mut self, text: &str) -> (usize, usize) {
+		let font = self.surface().font();
+		let width = text.split('\n').map(|line| font.line_width(line)).max().unwrap_or(0);
+
+		(width, font.line_height() * text.split('\n').count())
+	}
+
+	/// Paint some text with its anchor on the position specified, lines are aligned to each other the same way
+	fn text_anchored(&mut self, position: Point, text: &str, rgba: u32, anchor: Anchor) {
+		let size = self.measure_text(text);
+		let topleft = self.place_box(position, size, 0, anchor);
+
+		text_block(self, topleft, size.0, text, rgba, anchor.horizontal);
+	}
+
+	/// Draw text according to specifications and a box around it as well (give 1 pix of both x and y margin).
 	fn text_with_outline(&mut self, topleft: Point, text: &str, rgba: u32) {
-		let count = text.as_bytes().len();
-		for delta_x in 0..count * 10 + 2 {
-			let x = topleft.0 + delta_x;
-			for delta_y in 0..19 {
-				let y = topleft.1 + delta_y;
-
-				let colour = if delta_y == 0 || delta_x == 0 || delta_x == count * 10 + 1 || delta_y == 18 { rgba } else { self.background() };
-				self.colour(x, y, colour);
-			}
-		}
+		self.text_with_outline_anchored(topleft, text, rgba, Anchor::TOP_LEFT);
+	}
+
+	/// Draw text with a box around it, with the anchor of the box on the position specified
+	fn text_with_outline_anchored(&mut self, position: Point, text: &str, rgba: u32, anchor: Anchor) {
+		let (width, height) = self.measure_text(text);
+		let topleft = self.place_box(position, (width + 2, height + 2), 1, anchor);
+		let background = self.background();
 
-		self.text((topleft.0 + 1, topleft.1 + 1), text, rgba);
+		self.rect(topleft.0, topleft.1, topleft.0 + width + 1, topleft.1 + height + 1, rgba);
+		self.rect(topleft.0 + 1, topleft.1 + 1, topleft.0 + width, topleft.1 + height, background);
+
+		text_block(self, (topleft.0 + 1, topleft.1 + 1), width, text, rgba, anchor.horizontal);
 	}
 
-	/// Draw text according to specifications and a background behind it as well. Supports a single line only.
+	/// Draw text according to specifications and a background behind it as well.
 	fn text_with_background(&mut self, topleft: Point, text: &str, rgba: u32, background_rgba: u32) {
-		let count = text.as_bytes().len();
-		for delta_x in 0..count * 10 {
-			let x = topleft.0 + delta_x;
-			for delta_y in 0..17 {
-				let y = topleft.1 + delta_y;
-
-				self.colour(x, y, background_rgba);
-			}
+		self.text_with_background_anchored(topleft, text, rgba, background_rgba, Anchor::TOP_LEFT);
+	}
+
+	/// Draw text with a background behind it, with the anchor of the background on the position specified
+	fn text_with_background_anchored(&mut self, position: Point, text: &str, rgba: u32, background_rgba: u32, anchor: Anchor) {
+		let (width, height) = self.measure_text(text);
+		let topleft = self.place_box(position, (width, height), 0, anchor);
+
+		if width > 0 {
+			self.rect(topleft.0, topleft.1, topleft.0 + width - 1, topleft.1 + height - 1, background_rgba);
 		}
 
-		self.text((topleft.0, topleft.1), text, rgba);
+		text_block(self, topleft, width, text, rgba, anchor.horizontal);
+	}
+
+	/// Top left corner of a box of the size given with its anchor on the position, moved left to fit the width if needed.
+	/// The padding is the distance from the top of the box to the top of the text.
+	fn place_box(&mut self, position: Point, size: (usize, usize), padding: usize, anchor: Anchor) -> Point {
+		let x = match anchor.horizontal {
+			HorizontalAlign::Left => position.0,
+			HorizontalAlign::Centre => position.0.saturating_sub(size.0 / 2),
+			HorizontalAlign::Right => position.0.saturating_sub(size.0),
+		};
+
+		let y = match anchor.vertical {
+			VerticalAlign::Top => position.1,
+			VerticalAlign::Middle => position.1.saturating_sub(size.1 / 2),
+			VerticalAlign::Baseline => position.1.saturating_sub(padding + self.surface().font().ascent()),
+			VerticalAlign::Bottom => position.1.saturating_sub(size.1),
+		};
+
+		(x.min(self.width().saturating_sub(size.0)), y)
 	}
+}
 
-	/// Paint the buffer in a certain colour
-	fn colour_buffer(buffer: &mut Vec<u8>, area: usize, rgba: u32) {
-		let r = (rgba >> 24) as u8;
-		let g = (rgba >> 16) as u8;
-		let b = (rgba >> 8) as u8;
+/// Paints the lines of the text aligned within a block of the width given
+fn text_block<P: Painter + ?Sized>(painter: &mut P, topleft: Point, width: usize, text: &str, rgba: u32, align: HorizontalAlign) {
+	if !text.contains('\n') && align == HorizontalAlign::Left {
+		return painter.text(topleft, text, rgba);
+	}
 
-		let colours = [r, g, b];
+	let line_height = painter.line_height();
 
-		for aj in 0..area * 3 {
-			buffer.push(colours[aj % 3]);
-		}
+	for (row, line) in text.split('\n').enumerate() {
+		let spare = width - painter.measure_text(line).0;
+		let x = match align {
+			HorizontalAlign::Left => 0,
+			HorizontalAlign::Centre => spare / 2,
+			HorizontalAlign::Right => spare,
+		};
+
+		painter.text((topleft.0 + x, topleft.1 + row * line_height), line, rgba);
 	}
 }
@@ -9,11 +9,18 @@ pub struct BasicIndicativeLines<C> {
 	max_colour: u32,
 	min_colour: u32,
 	current_colour: u32,
+	stroke: Stroke,
 }
 
 impl<C> BasicIndicativeLines<C> {
 	pub fn new(max_colour: u32, min_colour: u32, current_colour: u32) -> BasicIndicativeLines<C> {
-		BasicIndicativeLines { _c: PhantomData, max_colour, min_colour, current_colour }
+		BasicIndicativeLines { _c: PhantomData, max_colour, min_colour, current_colour, stroke: Stroke::new() }
+	}
+
+	pub fn stroke(mut self, stroke: Stroke) -> BasicIndicativeLines<C> {
+		self.stroke = stroke;
+
+		self
 	}
 }
 
@@ -23,9 +30,9 @@ impl<C: Candle> RendererExtension for BasicIndicativeLines<C> {
 	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
 		let data = aggregate(data);
 
-		draw(buffer, data.h, self.max_colour);
-		draw(buffer, data.l, self.min_colour);
-		draw(buffer, data.c, self.current_colour);
+		draw(buffer, data.h, self.max_colour, &self.stroke, LabelPriority::Extreme);
+		draw(buffer, data.l, self.min_colour, &self.stroke, LabelPriority::Extreme);
+		draw(buffer, data.c, self.current_colour, &self.stroke, LabelPriority::Current);
 	}
 
 	fn lore_colour(&self) -> Option<u32> {
@@ -37,10 +44,11 @@ impl<C: Candle> RendererExtension for BasicIndicativeLines<C> {
 	}
 }
 
-fn draw(buffer: &mut ChartBuffer, price: f64, rgba: u32) {
+fn draw(buffer: &mut ChartBuffer, price: f64, rgba: u32, stroke: &Stroke, priority: LabelPriority) {
 	let p1 = buffer.data_to_coords(price, 0);
 	let p2 = buffer.data_to_coords(price, buffer.timeframe);
 
-	buffer.line(p1, p2, rgba);
-	buffer.text_with_outline((p2.0 + 3, p2.1 - 9), &format!("{:.1}", price), rgba);
+	buffer.stroke_line(p1, p2, rgba, stroke);
+	let label = buffer.price_format.format(price);
+	buffer.price_label(price, &label, rgba, true, priority);
 }
@@ -2,6 +2,7 @@ use std::marker::PhantomData;
 
 use model::*;
 use model::rex::ema::median_list;
+use model::rex::range_of;
 
 #[derive(Debug)]
 struct BandPoints {
@@ -15,19 +16,28 @@ pub struct BollingerBands<C> {
 	_c: PhantomData<C>,
 	periods: usize,
 	standard_deviations: usize,
-	line_colour: u32,
+	/// Taken from the theme if absent
+	line_colour: Option<u32>,
+	stroke: Stroke,
 }
 
-impl<C> BollingerBands<C> {
+impl<C: Candle> BollingerBands<C> {
 	pub fn new(periods: usize, standard_deviations: usize, line_colour: u32) -> BollingerBands<C> {
-		BollingerBands { _c: PhantomData, periods, standard_deviations, line_colour }
+		BollingerBands { _c: PhantomData, periods, standard_deviations, line_colour: Some(line_colour), stroke: Stroke::new() }
 	}
-}
 
-impl<C: Candle> RendererExtension for BollingerBands<C> {
-	type Candle = C;
+	/// Creates Bollinger bands coloured from the theme's indicator palette
+	pub fn themed(periods: usize, standard_deviations: usize) -> BollingerBands<C> {
+		BollingerBands { _c: PhantomData, periods, standard_deviations, line_colour: None, stroke: Stroke::new() }
+	}
 
-	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
+	pub fn stroke(mut self, stroke: Stroke) -> BollingerBands<C> {
+		self.stroke = stroke;
+
+		self
+	}
+
+	fn bands(&self, data: &[C]) -> Vec<BandPoints> {
 		let mut bands = vec![];
 
 		for i in self.periods..data.len() {
@@ -46,31 +56,46 @@ impl<C: Candle> RendererExtension for BollingerBands<C> {
 			bands.push(points);
 		}
 
-		let offset = ((self.periods as f64 + 0.5) * (buffer.timeframe as f64) / (data.len() as f64)) as i64;
+		bands
+	}
+}
 
-		for i in 0..(bands.len() - 1) {
-			let time = (i as i64 * buffer.timeframe / data.len() as i64) as i64 + offset;
-			let time_next_period = ((i as i64 + 1) * buffer.timeframe / data.len() as i64) as i64 + offset;
+impl<C: Candle> RendererExtension for BollingerBands<C> {
+	type Candle = C;
 
-			let p1_h = buffer.data_to_coords(bands[i].higher, time);
-			let p2_h = buffer.data_to_coords(bands[i + 1].higher, time_next_period);
+	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
+		let bands = self.bands(data);
 
-			buffer.line(p1_h, p2_h, self.line_colour);
+		// Each band sits in the middle of the candle following its window
+		let offset = buffer.time_scale.period / 2;
 
-			let p1_m = buffer.data_to_coords(bands[i].median, time);
-			let p2_m = buffer.data_to_coords(bands[i + 1].median, time_next_period);
+		let band_line = |buffer: &ChartBuffer, value: fn(&BandPoints) -> f64| {
+			bands.iter().enumerate()
+				.map(|(i, points)| buffer.data_to_coords(value(points), buffer.time_scale.candle_time(i + self.periods) + offset))
+				.collect::<Vec<_>>()
+		};
 
-			buffer.line(p1_m, p2_m, self.line_colour);
+		let higher = band_line(buffer, |points| points.higher);
+		let median = band_line(buffer, |points| points.median);
+		let lower = band_line(buffer, |points| points.lower);
 
-			let p1_l = buffer.data_to_coords(bands[i].lower, time);
-			let p2_l = buffer.data_to_coords(bands[i + 1].lower, time_next_period);
+		let colour = self.line_colour.unwrap_or_else(|| buffer.theme.indicator(buffer.palette_index));
 
-			buffer.line(p1_l, p2_l, self.line_colour);
-		}
+		buffer.polyline(&higher, colour, &self.stroke);
+		buffer.polyline(&median, colour, &self.stroke);
+		buffer.polyline(&lower, colour, &self.stroke);
 	}
 
 	fn lore_colour(&self) -> Option<u32> {
-		Some(self.line_colour)
+		self.line_colour
+	}
+
+	fn price_range(&self, data: &[C]) -> Option<(f64, f64)> {
+		range_of(self.bands(data).iter().flat_map(|points| vec![points.lower, points.higher]))
+	}
+
+	fn themed_lore_colour(&self, theme: &Theme, palette_index: usize) -> Option<u32> {
+		Some(self.line_colour.unwrap_or_else(|| theme.indicator(palette_index)))
 	}
 
 	fn name(&self) -> String {
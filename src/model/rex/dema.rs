@@ -1,6 +1,7 @@
 use model::*;
 use model::buffer::ChartBuffer;
 use model::rex::ema::*;
+use model::rex::range_of;
 
 #[derive(Clone, Debug)]
 pub struct DEMA<C> {
@@ -11,34 +12,50 @@ impl<C> DEMA<C> {
 	pub fn new(ema: EMA<C>) -> DEMA<C> {
 		DEMA { inner: ema }
 	}
+
+	pub fn stroke(mut self, stroke: Stroke) -> DEMA<C> {
+		self.inner.stroke = stroke;
+
+		self
+	}
+
+	/// Labels the last value on the price axis
+	pub fn value_label(mut self) -> DEMA<C> {
+		self.inner.value_label = true;
+
+		self
+	}
 }
 
 impl<C: Candle> RendererExtension for DEMA<C> {
 	type Candle = C;
 
 	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
-		let tf = buffer.timeframe;
-		let len = data.len();
-		let dema = {
-			let ema_buf = ema(&self.inner, &median_list(data));
-			let mut dema_buf = ema_buf.clone();
-			multply_all(&mut dema_buf, 2.);
+		let colour = self.inner.themed_colour(&buffer.theme, buffer.palette_index);
+		let dema = dema(&self.inner, data);
 
-			subtract(&mut dema_buf, &ema(&self.inner, &ema_buf));
+		let points = dema.iter().enumerate().skip(self.inner.periods)
+			.map(|(p, value)| buffer.data_to_coords(*value, buffer.time_scale.candle_time(p)))
+			.collect::<Vec<_>>();
 
-			dema_buf
-		};
+		buffer.polyline(&points, colour, &self.inner.stroke);
 
-		for p in self.inner.periods + 1..len {
-			let p1 = buffer.data_to_coords(dema[p - 1], (tf as f64 * ((p - 1) as f64 / len as f64)) as i64);
-			let p2 = buffer.data_to_coords(dema[p], (tf as f64 * (p as f64 / len as f64)) as i64);
-
-			buffer.line(p1, p2, self.inner.colour);
+		if let (true, Some(&last)) = (self.inner.value_label, dema.iter().skip(self.inner.periods).last()) {
+			let label = buffer.price_format.format(last);
+			buffer.price_label(last, &label, colour, true, LabelPriority::Indicator);
 		}
 	}
 
 	fn lore_colour(&self) -> Option<u32> {
-		Some(self.inner.colour)
+		self.inner.colour
+	}
+
+	fn price_range(&self, data: &[C]) -> Option<(f64, f64)> {
+		range_of(dema(&self.inner, data).into_iter().skip(self.inner.periods))
+	}
+
+	fn themed_lore_colour(&self, theme: &Theme, palette_index: usize) -> Option<u32> {
+		Some(self.inner.themed_colour(theme, palette_index))
 	}
 
 	fn name(&self) -> String {
@@ -46,9 +63,19 @@ impl<C: Candle> RendererExtension for DEMA<C> {
 	}
 }
 
+fn dema<C: Candle>(inner: &EMA<C>, data: &[C]) -> Vec<f64> {
+	let ema_buf = ema(inner, &median_list(data));
+	let mut dema_buf = ema_buf.clone();
+	multply_all(&mut dema_buf, 2.);
+
+	subtract(&mut dema_buf, &ema(inner, &ema_buf));
+
+	dema_buf
+}
+
 pub fn multply_all(buf: &mut [f64], factor: f64) {
-	for i in 0..buf.len() {
-		buf[i] *= factor;
+	for elem in buf.iter_mut() {
+		*elem *= factor;
 	}
 }
 
@@ -58,7 +85,7 @@ pub fn subtract(buf: &mut [f64], other: &[f64]) {
 		return;
 	}
 
-	for i in 0..len {
-		buf[i] -= other[i];
+	for (elem, other) in buf.iter_mut().zip(other) {
+		*elem -= other;
 	}
 }
\ No newline at end of file
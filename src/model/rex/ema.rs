@@ -2,18 +2,44 @@ use std::marker::PhantomData;
 
 use model::*;
 use model::buffer::ChartBuffer;
+use model::rex::range_of;
 
 #[derive(Clone, Debug)]
 pub struct EMA<C> {
 	_c: PhantomData<C>,
 	pub(crate) periods: usize,
 	pub(crate) smoothing_factor: f64,
-	pub(crate) colour: u32,
+	/// Taken from the theme if absent
+	pub(crate) colour: Option<u32>,
+	pub(crate) stroke: Stroke,
+	pub(crate) value_label: bool,
 }
 
 impl<C> EMA<C> {
 	pub fn new(periods: usize, smoothing_factor: f64, colour: u32) -> EMA<C> {
-		EMA { _c: PhantomData, periods, smoothing_factor, colour }
+		EMA { _c: PhantomData, periods, smoothing_factor, colour: Some(colour), stroke: Stroke::new(), value_label: false }
+	}
+
+	/// Creates an EMA coloured from the theme's indicator palette
+	pub fn themed(periods: usize, smoothing_factor: f64) -> EMA<C> {
+		EMA { _c: PhantomData, periods, smoothing_factor, colour: None, stroke: Stroke::new(), value_label: false }
+	}
+
+	pub(crate) fn themed_colour(&self, theme: &Theme, palette_index: usize) -> u32 {
+		self.colour.unwrap_or_else(|| theme.indicator(palette_index))
+	}
+
+	pub fn stroke(mut self, stroke: Stroke) -> EMA<C> {
+		self.stroke = stroke;
+
+		self
+	}
+
+	/// Labels the last value on the price axis
+	pub fn value_label(mut self) -> EMA<C> {
+		self.value_label = true;
+
+		self
 	}
 }
 
@@ -21,20 +47,32 @@ impl<C: Candle> RendererExtension for EMA<C> {
 	type Candle = C;
 
 	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
-		let tf = buffer.timeframe;
-		let len = data.len();
-		let ema = ema(&self, &median_list(data));
+		let ema = ema(self, &median_list(data));
+		let colour = self.themed_colour(&buffer.theme, buffer.palette_index);
 
-		for p in self.periods + 1..len {
-			let p1 = buffer.data_to_coords(ema[p - 1], (tf as f64 * ((p - 1) as f64 / len as f64)) as i64);
-			let p2 = buffer.data_to_coords(ema[p], (tf as f64 * (p as f64 / len as f64)) as i64);
+		let points = ema.iter().enumerate().skip(self.periods)
+			.map(|(p, value)| buffer.data_to_coords(*value, buffer.time_scale.candle_time(p)))
+			.collect::<Vec<_>>();
 
-			buffer.line(p1, p2, self.colour);
+		buffer.polyline(&points, colour, &self.stroke);
+
+		// Only label values that are drawn, data shorter than the period has none
+		if let (true, Some(&last)) = (self.value_label, ema.iter().skip(self.periods).last()) {
+			let label = buffer.price_format.format(last);
+			buffer.price_label(last, &label, colour, true, LabelPriority::Indicator);
 		}
 	}
 
 	fn lore_colour(&self) -> Option<u32> {
-		Some(self.colour)
+		self.colour
+	}
+
+	fn price_range(&self, data: &[C]) -> Option<(f64, f64)> {
+		range_of(ema(self, &median_list(data)).into_iter().skip(self.periods))
+	}
+
+	fn themed_lore_colour(&self, theme: &Theme, palette_index: usize) -> Option<u32> {
+		Some(self.themed_colour(theme, palette_index))
 	}
 
 	fn name(&self) -> String {
@@ -48,18 +86,18 @@ pub fn ema<C: Candle>(ema: &EMA<C>, data: &[f64]) -> Vec<f64> {
 	for point in 0..data.len() {
 		let mut numerator = 0.;
 		let mut denominator = 0.;
-		for i in if point > ema.periods { point - ema.periods } else { 0 }..point + 1 {
+		for (i, value) in data.iter().enumerate().take(point + 1).skip(point.saturating_sub(ema.periods)) {
 			let exponent = (point + 1) - i;
 			let weight = (1. - ema.smoothing_factor).powf(exponent as f64);
 
-			numerator += data[i] * weight;
+			numerator += value * weight;
 			denominator += weight;
 		}
 
 		buf.push(numerator / denominator);
 	}
 
-	return buf;
+	buf
 }
 
 pub fn median_of_ohlc<C: Candle>(ohlc: &C) -> f64 {
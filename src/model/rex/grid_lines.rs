@@ -1,7 +1,39 @@
+use std::fmt::Write;
 use std::marker::PhantomData;
 
+use chrono::{FixedOffset, TimeZone};
+
 use model::*;
-use utils::duration_string;
+use utils::{aligned_times, duration_string, nice_price_interval, nice_time_interval};
+
+/// How the vertical grid lines are labelled
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum TimeLabels {
+	/// Time elapsed until the right edge of the chart, e.g. "1d2h"
+	Relative,
+	/// Dates/times of the candles' timestamps, falls back to relative labels for candles without timestamps
+	Absolute {
+		/// strftime-style format of the labels, e.g. "%d %b %H:%M"
+		format: String,
+		/// Offset from UTC of the displayed times, in seconds
+		utc_offset: i32,
+	},
+}
+
+/// Target distances between grid lines for automatically picked intervals
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct GridDensity {
+	/// Target distance between price lines, in pixels
+	pub price_spacing: usize,
+	/// Target distance between time lines, in pixels
+	pub time_spacing: usize,
+}
+
+impl Default for GridDensity {
+	fn default() -> GridDensity {
+		GridDensity { price_spacing: 50, time_spacing: 150 }
+	}
+}
 
 #[derive(Clone, Debug)]
 pub struct GridLines<C> {
@@ -10,11 +42,33 @@ pub struct GridLines<C> {
 	label: bool,
 	price_interval: f64,
 	time_interval: i64,
+	time_labels: TimeLabels,
+	auto_density: Option<GridDensity>,
+	stroke: Stroke,
 }
 
 impl<C> GridLines<C> {
 	pub fn new(colour: u32, label: bool, price_interval: f64, time_interval: i64) -> GridLines<C> {
-		GridLines { _c: PhantomData, colour, label, price_interval, time_interval }
+		GridLines { _c: PhantomData, colour, label, price_interval, time_interval, time_labels: TimeLabels::Relative, auto_density: None, stroke: Stroke::new() }
+	}
+
+	/// Ignore the fixed intervals and pick human-friendly ones that space the lines out as specified
+	pub fn auto_intervals(mut self, density: GridDensity) -> GridLines<C> {
+		self.auto_density = Some(density);
+
+		self
+	}
+
+	pub fn time_labels(mut self, time_labels: TimeLabels) -> GridLines<C> {
+		self.time_labels = time_labels;
+
+		self
+	}
+
+	pub fn stroke(mut self, stroke: Stroke) -> GridLines<C> {
+		self.stroke = stroke;
+
+		self
 	}
 }
 
@@ -22,39 +76,32 @@ impl<C: Candle> RendererExtension for GridLines<C> {
 	type Candle = C;
 
 	fn apply(&self, buffer: &mut ChartBuffer, _data: &[C]) {
-		{
-			let mut price = round_start_price(&buffer, self.price_interval);
-			while price <= buffer.max_price {
-				let p1 = buffer.data_to_coords(price, 0);
-				let p2 = buffer.data_to_coords(price, buffer.timeframe);
-				buffer.line(p1, p2, self.colour);
-				if self.label {
-					buffer.text((p2.0 + 4, p2.1 - 8), &format!("{:.1}", price), self.colour);
-				}
+		let (price_interval, time_interval) = self.intervals(buffer);
 
-				price += self.price_interval;
-			}
-		}
+		let (prices, uniform) = self.price_lines(buffer, price_interval);
 
-		{
-			let mut time = buffer.timeframe;
-			for _ in 0..(time / self.time_interval) + 1 {
-				let p1 = {
-					let point = buffer.data_to_coords(buffer.min_price, time);
-					(point.0, point.1 + 15)
+		for price in prices {
+			let p1 = buffer.data_to_coords(price, 0);
+			let p2 = buffer.data_to_coords(price, buffer.timeframe);
+			buffer.stroke_line(p1, p2, self.colour, &self.stroke);
+			if self.label {
+				let label = if uniform {
+					buffer.price_format.format_tick(price, price_interval)
+				} else {
+					buffer.price_format.format(price)
 				};
-				let p2 = buffer.data_to_coords(buffer.max_price, time);
-
-				buffer.line(p1, p2, self.colour);
-
-				if self.label {
-					let elapsed = format!("{}", duration_string((buffer.timeframe - time) as u64));
-					buffer.text((p1.0 - 10, p1.1 + 2), &elapsed, self.colour);
-				}
 
-				time -= self.time_interval;
+				buffer.price_label(price, &label, self.colour, false, LabelPriority::Grid);
 			}
 		}
+
+		match (&self.time_labels, buffer.time_scale.start_time(), buffer.time_scale.end_time()) {
+			(TimeLabels::Absolute { format, utc_offset }, Some(start_time), Some(end_time)) =>
+				self.absolute_time_lines(buffer, time_interval, start_time, end_time, format, *utc_offset),
+			_ => self.relative_time_lines(buffer, time_interval),
+		}
+
+		self.time_breaks(buffer);
 	}
 
 	fn lore_colour(&self) -> Option<u32> {
@@ -66,6 +113,173 @@ impl<C: Candle> RendererExtension for GridLines<C> {
 	}
 }
 
+impl<C> GridLines<C> {
+	/// The (price, time) intervals between lines, in currency units and seconds
+	fn intervals(&self, buffer: &ChartBuffer) -> (f64, i64) {
+		match self.auto_density {
+			Some(density) => {
+				let plot_width = buffer.width() - (buffer.margin.left + buffer.margin.right);
+				let plot_height = buffer.height() - (buffer.margin.top + buffer.margin.bottom);
+
+				(
+					nice_price_interval(buffer.max_price - buffer.min_price, plot_height / density.price_spacing.max(1)),
+					nice_time_interval(buffer.time_scale.label_span(), plot_width / density.time_spacing.max(1)),
+				)
+			}
+			None => (self.price_interval, self.time_interval),
+		}
+	}
+
+	/// The prices to draw lines at, and whether they're uniformly spaced by the interval
+	fn price_lines(&self, buffer: &ChartBuffer, interval: f64) -> (Vec<f64>, bool) {
+		if buffer.price_scale == PriceScale::Logarithmic {
+			let target = match self.auto_density {
+				Some(density) => (buffer.height() - (buffer.margin.top + buffer.margin.bottom)) / density.price_spacing.max(1),
+				None => 5,
+			};
+
+			let lines = log_price_lines(buffer.min_price, buffer.max_price, target);
+			if lines.len() >= 3 {
+				return (lines, false);
+			}
+		}
+
+		let mut lines = vec![];
+
+		if interval <= 0. {
+			return (lines, true);
+		}
+
+		let start = round_start_price(buffer, interval);
+		let mut price = start;
+		while price <= buffer.max_price {
+			lines.push(price);
+			price = start + interval * lines.len() as f64;
+		}
+
+		(lines, true)
+	}
+
+	fn relative_time_lines(&self, buffer: &mut ChartBuffer, interval: i64) {
+		if interval <= 0 {
+			return;
+		}
+
+		let mut time = buffer.timeframe;
+		for _ in 0..(time / interval) + 1 {
+			let (p1, _) = self.time_line(buffer, time);
+
+			if self.label {
+				let elapsed = duration_string((buffer.timeframe - time) as u64);
+				buffer.text_anchored((p1.0, p1.1 + 2), &elapsed, self.colour, Anchor::TOP_CENTRE);
+			}
+
+			time -= interval;
+		}
+	}
+
+	fn absolute_time_lines(&self, buffer: &mut ChartBuffer, interval: i64, start_time: i64, end_time: i64, format: &str, utc_offset: i32) {
+		let offset = FixedOffset::east_opt(utc_offset).unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+
+		let mut lines: Vec<(i64, i64)> = vec![];
+		for timestamp in aligned_times(start_time, end_time, interval, utc_offset) {
+			if let Some(time) = buffer.time_scale.chart_time(timestamp) {
+				// Boundaries within a collapsed gap share a position, the latest one is the most relevant
+				if lines.last().is_some_and(|&(last, _)| last == time) {
+					lines.pop();
+				}
+				lines.push((time, timestamp));
+			}
+		}
+
+		for (time, timestamp) in lines {
+			let (p1, _) = self.time_line(buffer, time);
+
+			if self.label {
+				let mut label = String::new();
+				if let Some(dt) = offset.timestamp_opt(timestamp, 0).single() {
+					// Invalid format specifiers leave the label empty instead of panicking
+					if write!(label, "{}", dt.format(format)).is_err() {
+						label.clear();
+					}
+				}
+
+				buffer.text_anchored((p1.0, p1.1 + 2), &label, self.colour, Anchor::TOP_CENTRE);
+			}
+		}
+	}
+
+	/// Marks the collapsed gaps of the time axis with a pair of slashes across the bottom of the chart
+	fn time_breaks(&self, buffer: &mut ChartBuffer) {
+		let breaks = buffer.time_scale.breaks().to_vec();
+
+		for i in breaks {
+			// Centre the marker in the space between the candles, which fill 4/5 of their period
+			let time = buffer.time_scale.candle_time(i) - buffer.time_scale.period / 10;
+			let (x, y) = buffer.data_to_coords(buffer.min_price, time);
+
+			for dx in &[0, 4] {
+				buffer.line((x.saturating_sub(5) + dx, y + 6), (x.saturating_sub(1) + dx, y.saturating_sub(6)), self.colour);
+			}
+		}
+	}
+
+	/// Draws the vertical line of the time specified, returns the (bottom, top) points of it
+	fn time_line(&self, buffer: &mut ChartBuffer, time: i64) -> (Point, Point) {
+		let p1 = {
+			let point = buffer.data_to_coords(buffer.min_price, time);
+			(point.0, point.1 + 15)
+		};
+		let p2 = buffer.data_to_coords(buffer.max_price, time);
+
+		buffer.stroke_line(p1, p2, self.colour, &self.stroke);
+
+		(p1, p2)
+	}
+}
+
 fn round_start_price(buffer: &ChartBuffer, interval: f64) -> f64 {
-	buffer.min_price + interval - (buffer.min_price % interval)
+	((buffer.min_price / interval).floor() + 1.) * interval
+}
+
+/// Prices between min and max that follow the sparsest of the 1-2-5 style sequences with at least the target amount
+/// of lines in range, or the densest one if none of them do
+fn log_price_lines(min: f64, max: f64, target: usize) -> Vec<f64> {
+	let sequences: [&[f64]; 4] = [
+		&[1.],
+		&[1., 2., 5.],
+		&[1., 1.5, 2., 3., 5., 7.],
+		&[1., 2., 3., 4., 5., 6., 7., 8., 9.],
+	];
+
+	let mut lines = vec![];
+
+	for sequence in &sequences {
+		lines.clear();
+
+		for decade in (min.log10().floor() as i32)..(max.log10().ceil() as i32 + 1) {
+			for multiplier in sequence.iter() {
+				let price = multiplier * 10_f64.powi(decade);
+				if price >= min && price <= max {
+					lines.push(price);
+				}
+			}
+		}
+
+		if lines.len() >= target {
+			break;
+		}
+	}
+
+	lines
+}
+
+#[cfg(test)]
+#[test]
+fn log_price_lines_test() {
+	assert_eq!(log_price_lines(0.8, 120000., 5), vec![1., 10., 100., 1000., 10000., 100000.]);
+	assert_eq!(log_price_lines(80., 12000., 5), vec![100., 200., 500., 1000., 2000., 5000., 10000.]);
+	assert_eq!(log_price_lines(80., 1200., 5), vec![100., 150., 200., 300., 500., 700., 1000.]);
+	assert_eq!(log_price_lines(5600., 8000., 5), vec![6000., 7000., 8000.]);
+	assert!(log_price_lines(5600., 5800., 5).is_empty());
 }
@@ -8,33 +8,63 @@ use model::rex::ema::*;
 #[derive(Clone, Debug)]
 pub struct MACD<C> {
 	_c: PhantomData<C>,
-	divergence_colour: u32,
-	signal_colour: u32,
-	histogram_colour: u32,
-	label_colour: u32,
+	/// Each colour is taken from the theme if absent
+	divergence_colour: Option<u32>,
+	signal_colour: Option<u32>,
+	histogram_colour: Option<u32>,
+	label_colour: Option<u32>,
 	smoothing_factor: f64,
+	stroke: Stroke,
 }
 
 impl<C> MACD<C> {
 	pub fn new(divergence_colour: u32, signal_colour: u32, histogram_colour: u32, label_colour: u32, smoothing_factor: f64) -> MACD<C> {
 		MACD {
 			_c: PhantomData,
-			divergence_colour,
-			signal_colour,
-			histogram_colour,
-			label_colour,
+			divergence_colour: Some(divergence_colour),
+			signal_colour: Some(signal_colour),
+			histogram_colour: Some(histogram_colour),
+			label_colour: Some(label_colour),
 			smoothing_factor,
+			stroke: Stroke::new(),
 		}
 	}
+
+	/// Creates a MACD with lines from the theme's indicator palette, a histogram of the grid colour and labels of the text colour
+	pub fn themed(smoothing_factor: f64) -> MACD<C> {
+		MACD {
+			_c: PhantomData,
+			divergence_colour: None,
+			signal_colour: None,
+			histogram_colour: None,
+			label_colour: None,
+			smoothing_factor,
+			stroke: Stroke::new(),
+		}
+	}
+
+	/// Stroke style of the signal and divergence lines
+	pub fn stroke(mut self, stroke: Stroke) -> MACD<C> {
+		self.stroke = stroke;
+
+		self
+	}
 }
 
 impl<C: Candle> RendererExtension for MACD<C> {
 	type Candle = C;
 
 	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
-		let (divergence, signal, histogram) = {
-			let median_list = median_list(data);
+		let theme = &buffer.theme;
+		let divergence_colour = self.divergence_colour.unwrap_or_else(|| theme.indicator(buffer.palette_index));
+		let signal_colour = self.signal_colour.unwrap_or_else(|| theme.indicator(buffer.palette_index + 1));
+		let histogram_colour = self.histogram_colour.unwrap_or(theme.grid);
+		let label_colour = self.label_colour.unwrap_or(theme.text);
+		let legend_background = theme.legend_background;
+
+		let median_list = median_list(data);
 
+		let (divergence, signal, histogram) = {
 			let short = ema::<C>(&EMA::new(12, self.smoothing_factor, 0), &median_list);
 			let long = ema::<C>(&EMA::new(26, self.smoothing_factor, 0), &median_list);
 
@@ -49,7 +79,7 @@ impl<C: Candle> RendererExtension for MACD<C> {
 			(divergence, signal, histogram)
 		};
 
-		let (mut lowest, mut highest) = (divergence[0], divergence[0]);
+		let (mut lowest, mut highest) = (0., 0.);
 
 		for set in &[&divergence, &signal, &histogram] {
 			for number in *set {
@@ -62,28 +92,34 @@ impl<C: Candle> RendererExtension for MACD<C> {
 			}
 		}
 
-		if lowest > 0. {
-			lowest = 0.;
+		// Flat or missing values (up to rounding errors relative to the prices), keep the zero line in the middle
+		let largest_price = median_list.iter().fold(0., |largest: f64, price| largest.max(price.abs()));
+
+		if highest - lowest <= largest_price * 1e-9 {
+			lowest -= 1.;
+			highest += 1.;
 		}
 
 		let range = highest - lowest;
 
 		buffer.create_extension_strip(135, move |buffer| {
-			buffer.text((8, 8), &self.name(), self.label_colour);
-			buffer.text_with_background((8, 8 + 17), "MACD Divergence", self.divergence_colour, 0x7F7F7F7F);
-			buffer.text_with_background((8, 8 + 17 * 2), "MACD Signal", self.signal_colour, 0x7F7F7F7F);
+			let line_height = buffer.line_height();
+
+			buffer.text((8, 8), &self.name(), label_colour);
+			buffer.text_with_background((8, 8 + line_height), "MACD Divergence", divergence_colour, legend_background);
+			buffer.text_with_background((8, 8 + line_height * 2), "MACD Signal", signal_colour, legend_background);
 
-			let period = buffer.timeframe / data.len() as i64;
+			let period = buffer.time_scale.period;
 			let period_addition = 4. * period as f64 / 5.;
 
 			// Histogram
 			{
-				for i in 35..histogram.len() {
-					let time = period * i as i64 + (period_addition / 2.) as i64;
-					let p1 = buffer.data_to_coords((histogram[i] - lowest) / range, time - (period_addition / 12.).ceil() as i64);
+				for (i, value) in histogram.iter().enumerate().skip(35) {
+					let time = buffer.time_scale.candle_time(i) + (period_addition / 2.) as i64;
+					let p1 = buffer.data_to_coords((value - lowest) / range, time - (period_addition / 12.).ceil() as i64);
 					let p2 = buffer.data_to_coords(-lowest / range, time + (period_addition / 12.).floor() as i64);
 
-					buffer.rect_point(p1, p2, self.histogram_colour);
+					buffer.rect_point(p1, p2, histogram_colour);
 				}
 			}
 
@@ -93,23 +129,21 @@ impl<C: Candle> RendererExtension for MACD<C> {
 				let p1 = buffer.data_to_coords(prog, 0);
 				let p2 = buffer.data_to_coords(prog, buffer.timeframe);
 
-				buffer.line(p1, p2, self.label_colour);
-				buffer.text((p2.0 + 4, p2.1 - 8), "Zero", self.label_colour);
+				buffer.line(p1, p2, label_colour);
+				buffer.text_anchored((p2.0 + 4, p2.1), "Zero", label_colour, Anchor::MIDDLE_LEFT);
 			}
 
 			// Signal & divergence
 			{
-				for (data, colour, begin_pos) in &[(&signal, self.signal_colour, 35), (&divergence, self.divergence_colour, 26)] {
-					let len = data.len() as i64;
-
-					for i in *begin_pos + 1..len as usize {
-						let time1 = period * (i - 1) as i64 + (period_addition / 2.) as i64;
-						let time2 = period * i as i64 + (period_addition / 2.) as i64;
-						let p1 = buffer.data_to_coords((data[i - 1] - lowest) / range, time1 - (period_addition / 12.).ceil() as i64);
-						let p2 = buffer.data_to_coords((data[i] - lowest) / range, time2 - (period_addition / 12.).floor() as i64);
-
-						buffer.line(p1, p2, *colour);
-					}
+				for (data, colour, begin_pos) in &[(&signal, signal_colour, 35), (&divergence, divergence_colour, 26)] {
+					let points = data.iter().enumerate().skip(*begin_pos)
+						.map(|(i, value)| {
+							let time = buffer.time_scale.candle_time(i) + (period_addition / 2.) as i64;
+							buffer.data_to_coords((value - lowest) / range, time - (period_addition / 12.).ceil() as i64)
+						})
+						.collect::<Vec<_>>();
+
+					buffer.polyline(&points, *colour, &self.stroke);
 				}
 			}
 		});
@@ -119,6 +153,11 @@ impl<C: Candle> RendererExtension for MACD<C> {
 		None
 	}
 
+	/// The MACD line and the signal line
+	fn palette_slots(&self) -> usize {
+		2
+	}
+
 	fn name(&self) -> String {
 		format!("MACD(12, 26, 9, sf={})", self.smoothing_factor)
 	}
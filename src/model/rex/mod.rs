@@ -2,15 +2,16 @@ pub use buffer::*;
 pub use data::Candle;
 pub use model::rex::volume::Volume;
 pub use OHLCRenderOptions;
+pub use theme::Theme;
 
 pub use self::basic_indicative_lines::BasicIndicativeLines;
 pub use self::bollinger_bands::BollingerBands;
 pub use self::dema::DEMA;
 pub use self::ema::EMA;
-pub use self::grid_lines::GridLines;
+pub use self::grid_lines::{GridDensity, GridLines, TimeLabels};
 pub use self::macd::MACD;
 pub use self::no_extension::NoExtension;
-pub use self::ohlc_candles::OHLCCandles;
+pub use self::ohlc_candles::{CandleStyle, OHLCCandles};
 pub use self::rsi::RSI;
 
 pub trait RendererExtension {
@@ -20,9 +21,36 @@ pub trait RendererExtension {
 
 	fn lore_colour(&self) -> Option<u32>;
 
+	/// Lowest and highest price the extension draws on the main chart, for fitting the price range around overlays
+	fn price_range(&self, _data: &[Self::Candle]) -> Option<(f64, f64)> {
+		None
+	}
+
+	/// Colour of the legend entry when drawn with the theme as the extension at the position of the palette index,
+	/// for extensions that take their colour from the theme's indicator palette
+	fn themed_lore_colour(&self, _theme: &Theme, _palette_index: usize) -> Option<u32> {
+		self.lore_colour()
+	}
+
+	/// Colours of the theme's indicator palette the extension takes, from its palette index on, so the next extension
+	/// gets the colours after them
+	fn palette_slots(&self) -> usize {
+		1
+	}
+
 	fn name(&self) -> String;
 }
 
+/// Lowest and highest of the values, ignoring NaN and infinite values
+pub(crate) fn range_of<I: IntoIterator<Item=f64>>(values: I) -> Option<(f64, f64)> {
+	values.into_iter()
+		.filter(|value| value.is_finite())
+		.fold(None, |range, value| match range {
+			Some((low, high)) => Some((value.min(low), value.max(high))),
+			None => Some((value, value)),
+		})
+}
+
 pub mod basic_indicative_lines;
 pub mod bollinger_bands;
 pub mod dema;
@@ -2,16 +2,36 @@ use std::marker::PhantomData;
 
 use model::*;
 
+/// How candles are drawn
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CandleStyle {
+	/// Filled bodies from the open to the close with a wick from the high to the low
+	#[default]
+	Filled,
+	/// A line from the high to the low with the open ticked on the left and the close on the right
+	Bars,
+	/// Bodies are hollow when the candle closed above its open and filled otherwise, coloured by whether the
+	/// candle closed above the previous close
+	Hollow,
+}
+
 #[derive(Clone, Debug)]
 pub struct OHLCCandles<C> {
 	_c: PhantomData<C>,
 	up_colour: u32,
 	down_colour: u32,
+	style: CandleStyle,
 }
 
 impl<C> OHLCCandles<C> {
 	pub fn new(up_colour: u32, down_colour: u32) -> OHLCCandles<C> {
-		OHLCCandles { _c: PhantomData, up_colour, down_colour }
+		OHLCCandles { _c: PhantomData, up_colour, down_colour, style: CandleStyle::Filled }
+	}
+
+	pub fn style(mut self, style: CandleStyle) -> OHLCCandles<C> {
+		self.style = style;
+
+		self
 	}
 }
 
@@ -19,32 +39,72 @@ impl<C: Candle> RendererExtension for OHLCCandles<C> {
 	type Candle = C;
 
 	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
-		let period = buffer.timeframe / data.len() as i64;
+		let period = buffer.time_scale.period;
 		let period_addition = 4. * period as f64 / 5.;
 
-		for i in 0..data.len() {
-			let candle = &data[i];
-
+		for (i, candle) in data.iter().enumerate() {
 			let open = candle.open();
 			let close = candle.close();
 
-			let colour = if open > close { self.down_colour } else { self.up_colour };
+			let colour = match self.style {
+				CandleStyle::Hollow => {
+					let previous_close = if i > 0 { data[i - 1].close() } else { open };
 
-			// Main big block
-			{
-				let p1 = buffer.data_to_coords(open, period * i as i64);
-				let p2 = buffer.data_to_coords(close, ((period * (i as i64)) as f64 + period_addition) as i64);
+					if close < previous_close { self.down_colour } else { self.up_colour }
+				}
+				_ => if open > close { self.down_colour } else { self.up_colour },
+			};
 
-				buffer.rect_point(p1, p2, colour);
-			}
+			let left_most = buffer.time_scale.candle_time(i);
+			let right_most = (left_most as f64 + period_addition) as i64;
 
 			// Sticks
-			{
-				let time = period * i as i64 + (period_addition / 2.) as i64;
-				let p1 = buffer.data_to_coords(candle.high(), time - (period_addition / 12.).ceil() as i64);
-				let p2 = buffer.data_to_coords(candle.low(), time + (period_addition / 12.).floor() as i64);
+			let time = left_most + (period_addition / 2.) as i64;
+			let stick_left = time - (period_addition / 12.).ceil() as i64;
+			let stick_right = time + (period_addition / 12.).floor() as i64;
+			let high = buffer.data_to_coords(candle.high(), stick_left);
+			let low = buffer.data_to_coords(candle.low(), stick_right);
+
+			match self.style {
+				CandleStyle::Filled => {
+					// Main big block
+					let p1 = buffer.data_to_coords(open, left_most);
+					let p2 = buffer.data_to_coords(close, right_most);
+
+					buffer.rect_point(p1, p2, colour);
+					buffer.rect_point(high, low, colour);
+				}
+				CandleStyle::Bars => {
+					// Ticks are as thick as the stick is wide
+					let thickness = low.0 - high.0;
+					let tick = |point: Point| (point.0, point.1.saturating_sub(thickness / 2));
+
+					let open_left = tick(buffer.data_to_coords(open, left_most));
+					let close_right = tick(buffer.data_to_coords(close, right_most));
+
+					buffer.rect_point(high, low, colour);
+					buffer.rect(open_left.0, open_left.1, high.0, open_left.1 + thickness, colour);
+					buffer.rect(low.0, close_right.1, close_right.0, close_right.1 + thickness, colour);
+				}
+				CandleStyle::Hollow => {
+					let p1 = buffer.data_to_coords(open, left_most);
+					let p2 = buffer.data_to_coords(close, right_most);
+
+					if close > open {
+						let (top, bottom) = (p2.1, p1.1);
 
-				buffer.rect_point(p1, p2, colour);
+						// Outline of the body, the wick stops at its edges
+						buffer.rect(p1.0, top, p2.0, top, colour);
+						buffer.rect(p1.0, bottom, p2.0, bottom, colour);
+						buffer.rect(p1.0, top, p1.0, bottom, colour);
+						buffer.rect(p2.0, top, p2.0, bottom, colour);
+						buffer.rect(high.0, high.1, low.0, top, colour);
+						buffer.rect(high.0, bottom, low.0, low.1, colour);
+					} else {
+						buffer.rect_point(p1, p2, colour);
+						buffer.rect_point(high, low, colour);
+					}
+				}
 			}
 		}
 	}
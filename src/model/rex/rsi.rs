@@ -5,15 +5,36 @@ use model::*;
 #[derive(Clone, Debug)]
 pub struct RSI<C> {
 	_c: PhantomData<C>,
-	label_colour: u32,
-	colour: u32,
-	overbought_colour: u32,
-	oversold_colour: u32,
+	/// Each colour is taken from the theme if absent
+	label_colour: Option<u32>,
+	colour: Option<u32>,
+	overbought_colour: Option<u32>,
+	oversold_colour: Option<u32>,
+	stroke: Stroke,
 }
 
 impl<C> RSI<C> {
 	pub fn new(label_colour: u32, colour: u32, overbought_colour: u32, oversold_colour: u32) -> RSI<C> {
-		RSI { _c: PhantomData, label_colour, colour, overbought_colour, oversold_colour }
+		RSI {
+			_c: PhantomData,
+			label_colour: Some(label_colour),
+			colour: Some(colour),
+			overbought_colour: Some(overbought_colour),
+			oversold_colour: Some(oversold_colour),
+			stroke: Stroke::new(),
+		}
+	}
+
+	/// Creates an RSI with the text, indicator palette, up and down colours of the theme
+	pub fn themed() -> RSI<C> {
+		RSI { _c: PhantomData, label_colour: None, colour: None, overbought_colour: None, oversold_colour: None, stroke: Stroke::new() }
+	}
+
+	/// Stroke style of the RSI curve
+	pub fn stroke(mut self, stroke: Stroke) -> RSI<C> {
+		self.stroke = stroke;
+
+		self
 	}
 }
 
@@ -23,6 +44,12 @@ impl<C: Candle> RendererExtension for RSI<C> {
 	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
 		let periods = 10;
 
+		let theme = &buffer.theme;
+		let label_colour = self.label_colour.unwrap_or(theme.text);
+		let colour = self.colour.unwrap_or_else(|| theme.indicator(buffer.palette_index));
+		let overbought_colour = self.overbought_colour.unwrap_or(theme.up);
+		let oversold_colour = self.oversold_colour.unwrap_or(theme.down);
+
 		let mut rsi = vec![];
 
 		for i in periods..data.len() {
@@ -30,8 +57,8 @@ impl<C: Candle> RendererExtension for RSI<C> {
 				let mut gains = vec![];
 				let mut losses = vec![];
 
-				for j in i - periods..i {
-					let delta = data[j].close() - data[j].open();
+				for candle in &data[i - periods..i] {
+					let delta = candle.close() - candle.open();
 					if delta >= 0. {
 						gains.push(delta);
 						losses.push(0.);
@@ -44,11 +71,12 @@ impl<C: Candle> RendererExtension for RSI<C> {
 				avg(&gains[..]) / avg(&losses[..])
 			};
 
-			rsi.push(100. - 100. / (1. + rs));
+			// Without gains or losses the price didn't move either way
+			rsi.push(if rs.is_nan() { 50. } else { 100. - 100. / (1. + rs) });
 		}
 
 		buffer.create_extension_strip(175, move |buffer| {
-			buffer.text((8, 8), "RSI(10)", self.label_colour);
+			buffer.text((8, 8), "RSI(10)", label_colour);
 
 			// Lines and labels
 			{
@@ -56,36 +84,36 @@ impl<C: Candle> RendererExtension for RSI<C> {
 					let p1 = buffer.data_to_coords(0.7, 0);
 					let p2 = buffer.data_to_coords(0.7, buffer.timeframe);
 
-					buffer.line(p1, p2, self.overbought_colour);
-					buffer.text((p2.0 + 4, p2.1 - 8), "Overbought", self.overbought_colour);
+					buffer.line(p1, p2, overbought_colour);
+					buffer.text_anchored((p2.0 + 4, p2.1), "Overbought", overbought_colour, Anchor::MIDDLE_LEFT);
 				}
 				{
 					let p1 = buffer.data_to_coords(0.3, 0);
 					let p2 = buffer.data_to_coords(0.3, buffer.timeframe);
 
-					buffer.line(p1, p2, self.oversold_colour);
-					buffer.text((p2.0 + 4, p2.1 - 8), "Oversold", self.oversold_colour);
+					buffer.line(p1, p2, oversold_colour);
+					buffer.text_anchored((p2.0 + 4, p2.1), "Oversold", oversold_colour, Anchor::MIDDLE_LEFT);
 				}
 
 				for prog in [0, 50, 100].iter() {
 					let p1 = buffer.data_to_coords(*prog as f64 / 100., 0);
 					let p2 = buffer.data_to_coords(*prog as f64 / 100., buffer.timeframe);
 
-					buffer.line(p1, p2, self.label_colour);
-					buffer.text((p2.0 + 4, p2.1 - 8), &format!("{}", prog), self.label_colour);
+					buffer.line(p1, p2, label_colour);
+					buffer.text_anchored((p2.0 + 4, p2.1), &format!("{}", prog), label_colour, Anchor::MIDDLE_LEFT);
 				}
 			}
 
 			// Actual RSI Curve
 			{
-				let offset = ((periods as f64 + 0.5) * (buffer.timeframe as f64) / (data.len() as f64)) as i64;
+				// Each value sits in the middle of the candle following its window
+				let offset = buffer.time_scale.period / 2;
 
-				for i in 0..rsi.len() - 1 {
-					let p1 = buffer.data_to_coords(rsi[i] / 100., buffer.timeframe * i as i64 / data.len() as i64 + offset);
-					let p2 = buffer.data_to_coords(rsi[i + 1] / 100., buffer.timeframe * (i + 1) as i64 / data.len() as i64 + offset);
+				let points = rsi.iter().enumerate()
+					.map(|(i, value)| buffer.data_to_coords(value / 100., buffer.time_scale.candle_time(i + periods) + offset))
+					.collect::<Vec<_>>();
 
-					buffer.line(p1, p2, self.colour);
-				}
+				buffer.polyline(&points, colour, &self.stroke);
 			}
 		});
 	}
@@ -1,4 +1,4 @@
-use serde::export::PhantomData;
+use std::marker::PhantomData;
 
 use model::*;
 
@@ -9,12 +9,14 @@ impl<C: Candle> RendererExtension for TestLine<C> {
 	type Candle = C;
 
 	fn apply(&self, buffer: &mut ChartBuffer, _data: &[C]) {
-		buffer.line((0, 0), (1309, 649), 0xFFFF00FF);
-		buffer.line((0, 649), (1309, 0), 0xFFFF00FF);
-		buffer.line((0, 0), (0, 649), 0xFFFF00FF);
-		buffer.line((0, 649), (1309, 649), 0xFFFF00FF);
-		buffer.line((1309, 649), (1309, 0), 0xFFFF00FF);
-		buffer.line((0, 0), (1309, 0), 0xFFFF00FF);
+		let (r, b) = (buffer.width() - 1, buffer.height() - 1);
+
+		buffer.line((0, 0), (r, b), 0xFFFF00FF);
+		buffer.line((0, b), (r, 0), 0xFFFF00FF);
+		buffer.line((0, 0), (0, b), 0xFFFF00FF);
+		buffer.line((0, b), (r, b), 0xFFFF00FF);
+		buffer.line((r, b), (r, 0), 0xFFFF00FF);
+		buffer.line((0, 0), (r, 0), 0xFFFF00FF);
 	}
 
 	fn lore_colour(&self) -> Option<u32> {
@@ -1,4 +1,4 @@
-use serde::export::PhantomData;
+use std::marker::PhantomData;
 
 use model::*;
 
@@ -11,6 +11,15 @@ impl<C: Candle> RendererExtension for TestText<C> {
 	fn apply(&self, buffer: &mut ChartBuffer, _data: &[C]) {
 		buffer.text((0, 0), "DANKMEME", 0xFFFF00FF);
 		buffer.text((0, 60), "DANKMEME", 0xFFFF007F);
+
+		assert_eq!(buffer.measure_text("DANKMEME"), (80, 17));
+		assert_eq!(buffer.measure_text("DANK\nMEME!"), (50, 34));
+
+		let (width, height) = (buffer.width(), buffer.height());
+		buffer.text_with_outline_anchored((width / 2, height / 2), "Centred\nmulti-line\ntext", 0x00FFFFFF, Anchor::CENTRE);
+		buffer.text_with_background_anchored((width / 2, height / 2 + 60), "Right\naligned", 0xFFFFFFFF, 0xFF00007F, Anchor::new(HorizontalAlign::Right, VerticalAlign::Baseline));
+		// Kept inside the right edge
+		buffer.text_with_outline_anchored((width, 100), "Overflowing", 0xFF00FFFF, Anchor::MIDDLE_LEFT);
 	}
 
 	fn lore_colour(&self) -> Option<u32> {
@@ -5,15 +5,27 @@ use model::*;
 #[derive(Clone, Debug)]
 pub struct Volume<C> {
 	_c: PhantomData<C>,
-	label_colour: u32,
-	buy_colour: u32,
-	sell_colour: u32,
-	generic_colour: u32,
+	/// Each colour is taken from the theme if absent
+	label_colour: Option<u32>,
+	buy_colour: Option<u32>,
+	sell_colour: Option<u32>,
+	generic_colour: Option<u32>,
 }
 
 impl<C> Volume<C> {
 	pub fn new(label_colour: u32, buy_colour: u32, sell_colour: u32, generic_colour: u32) -> Volume<C> {
-		Volume { _c: PhantomData, label_colour, buy_colour, sell_colour, generic_colour }
+		Volume {
+			_c: PhantomData,
+			label_colour: Some(label_colour),
+			buy_colour: Some(buy_colour),
+			sell_colour: Some(sell_colour),
+			generic_colour: Some(generic_colour),
+		}
+	}
+
+	/// Creates a volume strip with the text, up, down and indicator palette colours of the theme
+	pub fn themed() -> Volume<C> {
+		Volume { _c: PhantomData, label_colour: None, buy_colour: None, sell_colour: None, generic_colour: None }
 	}
 }
 
@@ -21,11 +33,17 @@ impl<C: Candle> RendererExtension for Volume<C> {
 	type Candle = C;
 
 	fn apply(&self, buffer: &mut ChartBuffer, data: &[C]) {
+		let theme = &buffer.theme;
+		let label_colour = self.label_colour.unwrap_or(theme.text);
+		let buy_colour = self.buy_colour.unwrap_or(theme.up);
+		let sell_colour = self.sell_colour.unwrap_or(theme.down);
+		let generic_colour = self.generic_colour.unwrap_or_else(|| theme.indicator(buffer.palette_index));
+
+		let format = buffer.volume_format.clone();
 		let mut vols = vec![];
 		let mut max_vol = 0.;
 
-		for i in 0..data.len() {
-			let candle = &data[i];
+		for candle in data {
 			let b_vol = candle.buy_volume();
 			let total_vol = candle.total_volume();
 
@@ -35,8 +53,11 @@ impl<C: Candle> RendererExtension for Volume<C> {
 			vols.push((b_vol, total_vol));
 		}
 
+		// Portion of the strip's height
+		let height_of = move |vol: f64| if max_vol > 0. { vol / max_vol } else { 0. };
+
 		buffer.create_extension_strip(175, move |buffer| {
-			buffer.text((8, 8), "Volume", self.label_colour);
+			buffer.text((8, 8), "Volume", label_colour);
 
 			// Lines and labels
 			{
@@ -44,35 +65,37 @@ impl<C: Candle> RendererExtension for Volume<C> {
 					let p1 = buffer.data_to_coords(*prog, 0);
 					let p2 = buffer.data_to_coords(*prog, buffer.timeframe);
 
-					buffer.line(p1, p2, self.label_colour);
+					buffer.line(p1, p2, label_colour);
 
 					let price = prog * max_vol;
 
-					buffer.text_with_outline((p2.0 + 5, p2.1 - 9), &format!("{}", keep_msf(price, 3)), self.label_colour);
+					buffer.text_with_outline_anchored((p2.0 + 5, p2.1), &format.format(keep_msf(price, 3)), label_colour, Anchor::MIDDLE_LEFT);
 				}
 			}
 
 			// Rendering of the volume candles
 			{
-				let period = buffer.timeframe / data.len() as i64;
+				let period = buffer.time_scale.period;
 				let period_addition = 4. * period as f64 / 5.;
 
-				for i in 0..vols.len() {
-					let (b, t) = vols[i];
+				for (i, &(b, t)) in vols.iter().enumerate() {
+					if t <= 0. {
+						continue;
+					}
 
-					let left_most = period * i as i64;
-					let right_most = ((period * (i as i64)) as f64 + period_addition) as i64;
+					let left_most = buffer.time_scale.candle_time(i);
+					let right_most = (left_most as f64 + period_addition) as i64;
 
 					let bottom_left = buffer.data_to_coords(0., left_most);
 					if let Some(b) = b {
-						let mid_right = buffer.data_to_coords(b / max_vol, right_most);
+						let mid_right = buffer.data_to_coords(height_of(b), right_most);
 
-						buffer.rect_point(bottom_left, mid_right, self.buy_colour);
-						let top_left = buffer.data_to_coords(t / max_vol, left_most);
-						buffer.rect_point(mid_right, top_left, self.sell_colour);
+						buffer.rect_point(bottom_left, mid_right, buy_colour);
+						let top_left = buffer.data_to_coords(height_of(t), left_most);
+						buffer.rect_point(mid_right, top_left, sell_colour);
 					} else {
-						let top_right = buffer.data_to_coords(t / max_vol, right_most);
-						buffer.rect_point(bottom_left, top_right, self.generic_colour);
+						let top_right = buffer.data_to_coords(height_of(t), right_most);
+						buffer.rect_point(bottom_left, top_right, generic_colour);
 					}
 				}
 			}
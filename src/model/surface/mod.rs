@@ -0,0 +1,95 @@
+pub use self::raster::RasterSurface;
+pub use self::vector::VectorSurface;
+
+use model::font::Font;
+use model::painting::{Point, Stroke};
+
+pub mod raster;
+pub mod vector;
+
+/// Primitive drawing operations that every output backend implements, charts are drawn onto backends of other crates
+/// through `OHLCRenderOptions::render_to_surface`
+pub trait DrawingSurface {
+	fn width(&self) -> usize;
+
+	fn height(&self) -> usize;
+
+	/// Font of the text drawn
+	fn font(&self) -> &Font;
+
+	fn set_font(&mut self, font: Font);
+
+	/// Grow the surface downwards by the height in the background colour, alpha channel is ignored
+	fn extend(&mut self, height: usize, background: u32);
+
+	/// Colour a pixel by x and y coordinates
+	fn colour(&mut self, x: usize, y: usize, rgba: u32);
+
+	/// Fill a rectangle by the min/max x and y points (inclusive) and colour
+	fn rect(&mut self, x1: usize, y1: usize, x2: usize, y2: usize, rgba: u32);
+
+	/// Draw a line between two points
+	fn line(&mut self, p1: Point, p2: Point, rgba: u32);
+
+	/// Draw connected lines through the points with the stroke style specified
+	fn polyline(&mut self, points: &[Point], rgba: u32, stroke: &Stroke);
+
+	/// Paint some text in the colour provided, starting in the top left corner specified
+	fn text(&mut self, topleft: Point, text: &str, rgba: u32);
+}
+
+/// The rows of a surface from the top row on, drawn onto as if they were a surface of their own
+pub struct SubSurface<'a> {
+	surface: &'a mut dyn DrawingSurface,
+	top: usize,
+}
+
+impl<'a> SubSurface<'a> {
+	pub fn new(surface: &'a mut dyn DrawingSurface, top: usize) -> SubSurface<'a> {
+		SubSurface { surface, top }
+	}
+}
+
+impl<'a> DrawingSurface for SubSurface<'a> {
+	fn width(&self) -> usize {
+		self.surface.width()
+	}
+
+	fn height(&self) -> usize {
+		self.surface.height() - self.top
+	}
+
+	fn font(&self) -> &Font {
+		self.surface.font()
+	}
+
+	fn set_font(&mut self, font: Font) {
+		self.surface.set_font(font);
+	}
+
+	fn extend(&mut self, height: usize, background: u32) {
+		self.surface.extend(height, background);
+	}
+
+	fn colour(&mut self, x: usize, y: usize, rgba: u32) {
+		self.surface.colour(x, y + self.top, rgba);
+	}
+
+	fn rect(&mut self, x1: usize, y1: usize, x2: usize, y2: usize, rgba: u32) {
+		self.surface.rect(x1, y1 + self.top, x2, y2 + self.top, rgba);
+	}
+
+	fn line(&mut self, p1: Point, p2: Point, rgba: u32) {
+		self.surface.line((p1.0, p1.1 + self.top), (p2.0, p2.1 + self.top), rgba);
+	}
+
+	fn polyline(&mut self, points: &[Point], rgba: u32, stroke: &Stroke) {
+		let points: Vec<Point> = points.iter().map(|p| (p.0, p.1 + self.top)).collect();
+
+		self.surface.polyline(&points, rgba, stroke);
+	}
+
+	fn text(&mut self, topleft: Point, text: &str, rgba: u32) {
+		self.surface.text((topleft.0, topleft.1 + self.top), text, rgba);
+	}
+}
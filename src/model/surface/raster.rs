@@ -0,0 +1,383 @@
+use std::mem;
+
+use model::font::Font;
+use model::buffer::RgbBuffer;
+use model::painting::{LineCap, Point, Stroke};
+use model::surface::DrawingSurface;
+
+/// Draws the chart pixel by pixel into an RGB(8) buffer
+pub struct RasterSurface {
+	width: usize,
+	height: usize,
+	/// Whether lines, shapes and outline font text are drawn with anti-aliased edges instead of whole pixels
+	pub antialias: bool,
+	/// Font of the text drawn
+	pub font: Font,
+	/// Byte buffer of the actual image
+	pub buffer: Vec<u8>,
+}
+
+impl RasterSurface {
+	/// Creates a surface painted entirely in the background colour, alpha channel is ignored
+	pub fn new(width: usize, height: usize, background: u32) -> RasterSurface {
+		let r = (background >> 24) as u8;
+		let g = (background >> 16) as u8;
+		let b = (background >> 8) as u8;
+
+		let mut buffer = Vec::with_capacity(width * height * 3);
+
+		for _ in 0..width * height {
+			buffer.extend_from_slice(&[r, g, b]);
+		}
+
+		RasterSurface { width, height, antialias: false, font: Font::bitmap(), buffer }
+	}
+
+	pub fn antialiased(mut self, antialias: bool) -> RasterSurface {
+		self.antialias = antialias;
+
+		self
+	}
+
+	pub fn with_font(mut self, font: Font) -> RasterSurface {
+		self.font = font;
+
+		self
+	}
+
+	/// Colour a pixel with the alpha of the colour scaled down by the coverage (0 to 1)
+	fn blend(&mut self, x: usize, y: usize, rgba: u32, coverage: f64) {
+		let alpha = ((rgba & 0xFF) as f64 * coverage.clamp(0., 1.)).round() as u32;
+
+		self.colour(x, y, (rgba & !0xFF) | alpha);
+	}
+
+	/// Xiaolin Wu's line algorithm, spreads each step of the line over the 2 pixels nearest to it. The end pixels are
+	/// weighted by how much of them the line covers, so lines joined end to end don't double up at their joints.
+	fn antialiased_line(&mut self, p1: Point, p2: Point, rgba: u32) {
+		if p1 == p2 {
+			return self.colour(p1.0, p1.1, rgba);
+		}
+
+		let (mut x0, mut y0, mut x1, mut y1) = (p1.0 as f64, p1.1 as f64, p2.0 as f64, p2.1 as f64);
+
+		let steep = (y1 - y0).abs() > (x1 - x0).abs();
+		if steep {
+			mem::swap(&mut x0, &mut y0);
+			mem::swap(&mut x1, &mut y1);
+		}
+		if x0 > x1 {
+			mem::swap(&mut x0, &mut x1);
+			mem::swap(&mut y0, &mut y1);
+		}
+
+		let gradient = (y1 - y0) / (x1 - x0);
+
+		// Plots the 2 pixels straddling the line at the column, with the coverage of the column
+		let mut plot = |x: f64, y: f64, coverage: f64| {
+			let (near, far) = (y.floor(), y.floor() + 1.);
+			let fraction = y - near;
+
+			for &(row, row_coverage) in &[(near, 1. - fraction), (far, fraction)] {
+				if row < 0. || row_coverage * coverage <= 0. {
+					continue;
+				}
+
+				let (x, row) = (x as usize, row as usize);
+
+				if steep {
+					self.blend(row, x, rgba, row_coverage * coverage);
+				} else {
+					self.blend(x, row, rgba, row_coverage * coverage);
+				}
+			}
+		};
+
+		// The end pixels are covered by the half of them on the line's side of their centre
+		let x_start = x0.round();
+		let x_end = x1.round();
+		let start_gap = 1. - (x0 + 0.5).fract();
+		let end_gap = (x1 + 0.5).fract();
+
+		plot(x_start, y0 + gradient * (x_start - x0), start_gap);
+		plot(x_end, y0 + gradient * (x_end - x0), end_gap);
+
+		let mut x = x_start + 1.;
+		while x < x_end {
+			plot(x, y0 + gradient * (x - x0), 1.);
+			x += 1.;
+		}
+	}
+
+	/// Fills the area a segment of a wide line covers, pixel centres sit on whole coordinates
+	fn wide_segment(&mut self, a: (f64, f64), b: (f64, f64), width: f64, caps: (LineCap, LineCap), rgba: u32) {
+		let half = width / 2.;
+		let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+		let length = (dx * dx + dy * dy).sqrt();
+		let (ux, uy) = if length > 0. { (dx / length, dy / length) } else { (1., 0.) };
+
+		let extension = |cap| if cap == LineCap::Butt { 0. } else { half };
+		let (start_extension, end_extension) = (extension(caps.0), extension(caps.1));
+
+		let reach = width + 1.;
+		let min_x = (a.0.min(b.0) - reach).max(0.) as usize;
+		let min_y = (a.1.min(b.1) - reach).max(0.) as usize;
+		let max_x = ((a.0.max(b.0) + reach) as usize).min(self.width.saturating_sub(1));
+		let max_y = ((a.1.max(b.1) + reach) as usize).min(self.height.saturating_sub(1));
+
+		for y in min_y..max_y + 1 {
+			for x in min_x..max_x + 1 {
+				let (px, py) = (x as f64 - a.0, y as f64 - a.1);
+				let along = px * ux + py * uy;
+
+				// Coverage approximated by how far inside the edge of the shape the pixel centre is
+				let coverage = if along < 0. && caps.0 == LineCap::Round {
+					half + 0.5 - (px * px + py * py).sqrt()
+				} else if along > length && caps.1 == LineCap::Round {
+					let (qx, qy) = (x as f64 - b.0, y as f64 - b.1);
+					half + 0.5 - (qx * qx + qy * qy).sqrt()
+				} else {
+					let across = (px * uy - py * ux).abs();
+					(half + 0.5 - across).min(along + start_extension + 0.5).min(length + end_extension + 0.5 - along)
+				};
+
+				if self.antialias {
+					if coverage > 0. {
+						self.blend(x, y, rgba, coverage);
+					}
+				} else if coverage >= 0.5 {
+					self.colour(x, y, rgba);
+				}
+			}
+		}
+	}
+
+	pub fn into_rgb_buffer(self) -> RgbBuffer {
+		RgbBuffer {
+			width: self.width,
+			height: self.height,
+			buffer: self.buffer,
+		}
+	}
+}
+
+impl DrawingSurface for RasterSurface {
+	fn width(&self) -> usize {
+		self.width
+	}
+
+	fn height(&self) -> usize {
+		self.height
+	}
+
+	fn font(&self) -> &Font {
+		&self.font
+	}
+
+	fn set_font(&mut self, font: Font) {
+		self.font = font;
+	}
+
+	fn extend(&mut self, height: usize, background: u32) {
+		let rgb = [(background >> 24) as u8, (background >> 16) as u8, (background >> 8) as u8];
+
+		for _ in 0..self.width * height {
+			self.buffer.extend_from_slice(&rgb);
+		}
+
+		self.height += height;
+	}
+
+	fn colour(&mut self, x: usize, y: usize, rgba: u32) {
+		if x >= self.width || y >= self.height {
+			return;
+		}
+
+		// Weird casts because I wanna strip the first 24 bits
+		let alpha = (rgba as u8) as f64 / 255.;
+
+		for j in 0..3 {
+			let i = (x + y * self.width) * 3 + j;
+
+			let applied_colour = {
+				let colour = (rgba >> (24 - 8 * j)) as u8;
+				if alpha >= 0.96 { // Lazy if opacity is >= 96%
+					colour
+				} else if alpha <= 0.04 { // Lazy if opacity is <= 4%
+					continue;
+				} else {
+					let bgc = self.buffer[i];
+
+					(((alpha * colour as f64) + ((1. - alpha) * bgc as f64)).round()) as u8
+				}
+			};
+
+			self.buffer[i] = applied_colour;
+		}
+	}
+
+	fn rect(&mut self, mut x1: usize, mut y1: usize, mut x2: usize, mut y2: usize, rgba: u32) {
+		if x1 > x2 {
+			mem::swap(&mut x1, &mut x2);
+		}
+		if y1 > y2 {
+			mem::swap(&mut y1, &mut y2);
+		}
+
+		for x in x1..(x2 + 1) {
+			for y in y1..(y2 + 1) {
+				self.colour(x, y, rgba);
+			}
+		}
+	}
+
+	fn line(&mut self, mut p1: Point, mut p2: Point, rgba: u32) {
+		if self.antialias {
+			return self.antialiased_line(p1, p2, rgba);
+		}
+
+		let mut pixels = vec![];
+
+		if p1.0 > p2.0 {
+			mem::swap(&mut p1, &mut p2);
+		}
+
+		let adjacent = (p2.0 as i64 - p1.0 as i64) as f64;
+		let opposite = (p2.1 as i64 - p1.1 as i64) as f64;
+		let tan = opposite / adjacent;
+
+		for x in p1.0..p2.0 {
+			let y = (p1.1 as f64 + if (x - p1.0) != 0 { tan * (x - p1.0) as f64 } else { 0. }) as usize;
+			pixels.push((x, y));
+		}
+
+		if p1.1 > p2.1 {
+			mem::swap(&mut p1, &mut p2);
+		}
+
+		for y in p1.1..p2.1 {
+			let x = (p1.0 as f64 + if tan != 0. { (y - p1.1) as f64 / tan } else { 0. }) as usize;
+			pixels.push((x, y));
+		}
+
+		pixels.dedup_by(|a, b| a == b);
+
+		for (x, y) in pixels {
+			self.colour(x, y, rgba);
+		}
+	}
+
+	fn polyline(&mut self, points: &[Point], rgba: u32, stroke: &Stroke) {
+		if stroke.is_plain() {
+			for pair in points.windows(2) {
+				self.line(pair[0], pair[1], rgba);
+			}
+			return;
+		}
+
+		let width = stroke.width as f64;
+		let cap = stroke.effective_cap();
+		// Lines of even widths can't be centred on a pixel, so they are centred between two
+		let shift = if stroke.width % 2 == 0 { 0.5 } else { 0. };
+		let segments = points.len().saturating_sub(1);
+
+		// Distance along the whole line where the current segment starts
+		let mut distance = 0.;
+
+		for (i, pair) in points.windows(2).enumerate() {
+			let a = (pair[0].0 as f64 + shift, pair[0].1 as f64 + shift);
+			let b = (pair[1].0 as f64 + shift, pair[1].1 as f64 + shift);
+			let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+			let at = |d: f64| {
+				let t = if length > 0. { (d - distance) / length } else { 0. };
+				(a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+			};
+
+			// Corners between segments are rounded so wide lines join without notches
+			let start_cap = if i == 0 { cap } else { LineCap::Round };
+			let end_cap = if i + 1 == segments { cap } else { LineCap::Round };
+
+			match stroke.pattern() {
+				Some((dash_length, gap_length)) if dash_length + gap_length > 0. => {
+					let period = dash_length + gap_length;
+					let mut dash_start = (distance / period).floor() * period;
+
+					while dash_start <= distance + length {
+						let start = dash_start.max(distance);
+						let end = (dash_start + dash_length).min(distance + length);
+
+						// Dashes on a corner are drawn by both segments, dots only by the one they start in
+						let drawn = if dash_length > 0. {
+							start < end || (start == end && length == 0.)
+						} else {
+							start == dash_start && (start < distance + length || i + 1 == segments)
+						};
+
+						if drawn {
+							let caps = (
+								if start == dash_start { cap } else { start_cap },
+								if end == dash_start + dash_length { cap } else { end_cap });
+
+							self.wide_segment(at(start), at(end), width, caps, rgba);
+						}
+
+						dash_start += period;
+					}
+				}
+				_ => self.wide_segment(a, b, width, (start_cap, end_cap), rgba),
+			}
+
+			distance += length;
+		}
+	}
+
+	fn text(&mut self, topleft: Point, text: &str, rgba: u32) {
+		let font = self.font.clone();
+
+		// Outline glyphs only get smooth edges when anti-aliased, the alpha of the bitmap font is part of its design
+		let smooth = self.antialias || font.is_bitmap();
+
+		font.rasterise(topleft, text, |x, y, coverage| {
+			let coverage = if smooth { coverage } else if coverage >= 0.5 { 1. } else { return };
+			// Coverage in 1/255 steps, which is exactly the alpha of the bitmap font
+			let coverage = (coverage * 255.).round() as f64;
+			let a = (((rgba as u8) as f64 / 255.) * coverage) as u32;
+
+			self.colour(x, y, ((rgba >> 8) << 8) + a);
+		});
+	}
+}
+
+#[cfg(test)]
+fn pixel(surface: &RasterSurface, x: usize, y: usize) -> u8 {
+	surface.buffer[(x + y * surface.width) * 3]
+}
+
+#[cfg(test)]
+#[test]
+fn antialiased_line_test() {
+	let mut surface = RasterSurface::new(12, 8, 0x000000FF).antialiased(true);
+	surface.line((1, 2), (9, 2), 0xFFFFFFFF);
+
+	// Half of each end pixel is on the line
+	assert_eq!((pixel(&surface, 1, 2), pixel(&surface, 5, 2), pixel(&surface, 9, 2)), (128, 255, 128));
+	assert_eq!((pixel(&surface, 0, 2), pixel(&surface, 10, 2), pixel(&surface, 5, 3)), (0, 0, 0));
+
+	// Steep lines spread over the columns either side
+	surface.line((2, 7), (4, 1), 0xFFFFFFFF);
+	assert_eq!((pixel(&surface, 2, 5), pixel(&surface, 3, 5)), (85, 170));
+}
+
+#[cfg(test)]
+#[test]
+fn text_coverage_test() {
+	let draw = |antialias: bool| {
+		let mut surface = RasterSurface::new(40, 30, 0x000000FF).antialiased(antialias).with_font(Font::embedded(20.));
+		surface.text((2, 2), "S", 0xFFFFFFFF);
+
+		surface.buffer.iter().step_by(3).filter(|&&value| value > 0 && value < 255).count()
+	};
+
+	assert!(draw(true) > 0);
+	assert_eq!(draw(false), 0);
+}
@@ -0,0 +1,180 @@
+use model::font::Font;
+use model::painting::{LineCap, Point, Stroke};
+use model::surface::DrawingSurface;
+
+/// Records the chart as SVG elements, so it stays crisp at any zoom level
+pub struct VectorSurface {
+	width: usize,
+	height: usize,
+	/// Whether lines are drawn with anti-aliased edges, otherwise the viewer is asked to keep them crisp
+	pub antialias: bool,
+	/// Font of the text drawn, only its family and metrics end up in the SVG
+	pub font: Font,
+	elements: Vec<String>,
+}
+
+impl VectorSurface {
+	/// Creates a surface with a rectangle of the background colour as its first element
+	pub fn new(width: usize, height: usize, background: u32) -> VectorSurface {
+		let mut surface = VectorSurface { width, height, antialias: true, font: Font::bitmap(), elements: vec![] };
+
+		surface.elements.push(format!(r#"<rect width="{}" height="{}" {}/>"#, width, height, fill(background | 0xFF)));
+
+		surface
+	}
+
+	pub fn antialiased(mut self, antialias: bool) -> VectorSurface {
+		self.antialias = antialias;
+
+		self
+	}
+
+	pub fn with_font(mut self, font: Font) -> VectorSurface {
+		self.font = font;
+
+		self
+	}
+
+	/// Serialises the recorded elements into a standalone SVG document
+	pub fn to_svg(&self) -> String {
+		let mut svg = format!(
+			r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+			self.width, self.height);
+		svg.push('\n');
+
+		for element in &self.elements {
+			svg += element;
+			svg.push('\n');
+		}
+
+		svg += "</svg>\n";
+
+		svg
+	}
+}
+
+impl DrawingSurface for VectorSurface {
+	fn width(&self) -> usize {
+		self.width
+	}
+
+	fn height(&self) -> usize {
+		self.height
+	}
+
+	fn font(&self) -> &Font {
+		&self.font
+	}
+
+	fn set_font(&mut self, font: Font) {
+		self.font = font;
+	}
+
+	fn extend(&mut self, height: usize, background: u32) {
+		self.elements.push(format!(r#"<rect y="{}" width="{}" height="{}" {}/>"#, self.height, self.width, height, fill(background | 0xFF)));
+
+		self.height += height;
+	}
+
+	fn colour(&mut self, x: usize, y: usize, rgba: u32) {
+		self.rect(x, y, x, y, rgba);
+	}
+
+	fn rect(&mut self, x1: usize, y1: usize, x2: usize, y2: usize, rgba: u32) {
+		let (x, w) = if x1 > x2 { (x2, x1 - x2) } else { (x1, x2 - x1) };
+		let (y, h) = if y1 > y2 { (y2, y1 - y2) } else { (y1, y2 - y1) };
+
+		// Pixel coordinates are inclusive on both ends
+		self.elements.push(format!(
+			r#"<rect x="{}" y="{}" width="{}" height="{}" {} shape-rendering="crispEdges"/>"#,
+			x, y, w + 1, h + 1, fill(rgba)));
+	}
+
+	fn line(&mut self, p1: Point, p2: Point, rgba: u32) {
+		// Offset by half a pixel so the stroke covers the pixels it passes through
+		self.elements.push(format!(
+			r#"<line x1="{}.5" y1="{}.5" x2="{}.5" y2="{}.5" {}{}/>"#,
+			p1.0, p1.1, p2.0, p2.1, stroke(rgba), if self.antialias { "" } else { r#" shape-rendering="crispEdges""# }));
+	}
+
+	fn polyline(&mut self, points: &[Point], rgba: u32, style: &Stroke) {
+		if style.is_plain() {
+			for pair in points.windows(2) {
+				self.line(pair[0], pair[1], rgba);
+			}
+			return;
+		}
+
+		// Lines of even widths are centred between pixels, odd ones on the pixel centre
+		let offset = if style.width % 2 == 0 { 1. } else { 0.5 };
+		let points = points.iter()
+			.map(|p| format!("{},{}", p.0 as f64 + offset, p.1 as f64 + offset))
+			.collect::<Vec<_>>()
+			.join(" ");
+
+		let cap = match style.effective_cap() {
+			LineCap::Butt => "butt",
+			LineCap::Round => "round",
+			LineCap::Square => "square",
+		};
+
+		let dash = match style.pattern() {
+			Some((dash_length, gap_length)) => format!(r#" stroke-dasharray="{} {}""#, dash_length, gap_length),
+			None => String::new(),
+		};
+
+		self.elements.push(format!(
+			r#"<polyline points="{}" fill="none" {} stroke-width="{}" stroke-linecap="{}" stroke-linejoin="round"{}{}/>"#,
+			points, stroke(rgba), style.width, cap, dash, if self.antialias { "" } else { r#" shape-rendering="crispEdges""# }));
+	}
+
+	fn text(&mut self, topleft: Point, text: &str, rgba: u32) {
+		let (line_height, ascent) = (self.font.line_height(), self.font.ascent());
+
+		for (row, line) in text.split('\n').enumerate() {
+			if line.is_empty() {
+				continue;
+			}
+
+			// Stretched to the width measured from the font, so the layout matches the raster output
+			self.elements.push(format!(
+				r#"<text x="{}" y="{}" textLength="{}" lengthAdjust="spacingAndGlyphs" font-family="{}" font-size="{}" xml:space="preserve" {}>{}</text>"#,
+				topleft.0, topleft.1 + line_height * row + ascent, self.font.line_width(line), escape(self.font.family_name()),
+				self.font.em_size(), fill(rgba), escape(line)));
+		}
+	}
+}
+
+fn fill(rgba: u32) -> String {
+	paint("fill", rgba)
+}
+
+fn stroke(rgba: u32) -> String {
+	paint("stroke", rgba)
+}
+
+fn paint(attribute: &str, rgba: u32) -> String {
+	let alpha = rgba as u8;
+
+	if alpha == 0xFF {
+		format!(r##"{}="#{:06X}""##, attribute, rgba >> 8)
+	} else {
+		format!(r##"{0}="#{1:06X}" {0}-opacity="{2:.3}""##, attribute, rgba >> 8, alpha as f64 / 255.)
+	}
+}
+
+fn escape(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+
+	for c in text.chars() {
+		match c {
+			'&' => escaped += "&amp;",
+			'<' => escaped += "&lt;",
+			'>' => escaped += "&gt;",
+			'"' => escaped += "&quot;",
+			_ => escaped.push(c),
+		}
+	}
+
+	escaped
+}
@@ -0,0 +1,189 @@
+use Candle;
+
+/// How candles carrying timestamps are laid out along the time axis
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TimeAxis {
+	/// Candles are placed by their timestamps, missing candles are left as empty space
+	Continuous,
+	/// Candles are placed next to each other, gaps in the timestamps (e.g. weekends) are collapsed and marked on the axis
+	Compressed,
+	/// Candles are placed next to each other without marking gaps, for candles covering irregular amounts of time such
+	/// as tick, volume and dollar bars. Their timestamps may repeat and only label the axis.
+	Sequential,
+}
+
+/// Maps candles and timestamps onto the time of the chart, in seconds from its left edge
+#[derive(Clone, Debug)]
+pub struct TimeScale {
+	/// The amount of time each candle represents, in seconds
+	pub period: i64,
+	axis: TimeAxis,
+	/// Chart time of where each candle starts
+	positions: Vec<i64>,
+	/// Unix timestamps of each candle, only if every candle carries one
+	timestamps: Option<Vec<i64>>,
+	/// Indices of the candles preceded by a collapsed gap
+	breaks: Vec<usize>,
+}
+
+impl TimeScale {
+	pub fn new<C: Candle>(data: &[C], period: i64, axis: TimeAxis) -> TimeScale {
+		let timestamps = data.iter()
+			.map(Candle::timestamp)
+			.collect::<Option<Vec<i64>>>()
+			// Unordered timestamps can't be laid out, so treat them as missing
+			.filter(|ts| ts.windows(2).all(|pair| pair[0] < pair[1] || (axis == TimeAxis::Sequential && pair[0] == pair[1])));
+
+		let by_index = || (0..data.len()).map(|i| period * i as i64).collect();
+
+		let (positions, breaks) = match timestamps {
+			Some(ref ts) if axis == TimeAxis::Continuous => (ts.iter().map(|t| t - ts[0]).collect(), vec![]),
+			Some(ref ts) if axis == TimeAxis::Compressed => (by_index(), (1..ts.len()).filter(|&i| ts[i] - ts[i - 1] > period).collect()),
+			_ => (by_index(), vec![]),
+		};
+
+		TimeScale { period, axis, positions, timestamps, breaks }
+	}
+
+	/// Chart time of where the candle at the index starts
+	pub fn candle_time(&self, index: usize) -> i64 {
+		match self.positions.get(index) {
+			Some(&time) => time,
+			None => self.period * index as i64,
+		}
+	}
+
+	/// The amount of time the chart covers, in seconds, charts without candles cover one period
+	pub fn timeframe(&self) -> i64 {
+		self.positions.last().map_or(self.period, |last| last + self.period)
+	}
+
+	/// Unix timestamp of the left edge of the chart
+	pub fn start_time(&self) -> Option<i64> {
+		self.timestamps.as_ref().and_then(|ts| ts.first().cloned())
+	}
+
+	/// Unix timestamp of the right edge of the chart, or of the start of the last candle on sequential axes where the
+	/// period isn't a duration
+	pub fn end_time(&self) -> Option<i64> {
+		let extra = if self.axis == TimeAxis::Sequential { 0 } else { self.period };
+
+		self.timestamps.as_ref().and_then(|ts| ts.last().map(|last| last + extra))
+	}
+
+	/// Seconds the time lines are spread over, which on sequential axes is the time between their first and last candle
+	pub fn label_span(&self) -> i64 {
+		match (self.axis, self.start_time(), self.end_time()) {
+			(TimeAxis::Sequential, Some(start), Some(end)) => (end - start).max(1),
+			_ => self.timeframe(),
+		}
+	}
+
+	/// Chart time of a unix timestamp, timestamps within a collapsed gap snap to the start of the following candle
+	pub fn chart_time(&self, timestamp: i64) -> Option<i64> {
+		let ts = self.timestamps.as_ref()?;
+		let start = *ts.first()?;
+
+		if timestamp < start || timestamp > self.end_time()? {
+			return None;
+		}
+
+		if self.axis == TimeAxis::Continuous {
+			return Some(timestamp - start);
+		}
+
+		// Index of the last candle starting at or before the timestamp
+		let index = ts.partition_point(|&t| t <= timestamp) - 1;
+
+		if self.axis == TimeAxis::Sequential {
+			// Candles stretch over the time until the next one starts
+			let duration = ts.get(index + 1).map_or(self.period, |next| next - ts[index]);
+			let offset = (timestamp - ts[index]).min(duration);
+
+			return Some(self.positions[index] + self.period * offset / duration.max(1));
+		}
+
+		if timestamp - ts[index] <= self.period || index + 1 == ts.len() {
+			Some(self.positions[index] + (timestamp - ts[index]).min(self.period))
+		} else {
+			Some(self.positions[index + 1])
+		}
+	}
+
+	/// Indices of the candles preceded by a collapsed gap
+	pub fn breaks(&self) -> &[usize] {
+		&self.breaks
+	}
+}
+
+#[cfg(test)]
+fn candles_at(timestamps: &[i64]) -> Vec<::model::data::OHLC> {
+	timestamps.iter().map(|&t| ::model::data::OHLC { t: Some(t), ..Default::default() }).collect()
+}
+
+#[cfg(test)]
+#[test]
+fn continuous_time_scale_test() {
+	let scale = TimeScale::new(&candles_at(&[1000, 1060, 1300, 1360]), 60, TimeAxis::Continuous);
+
+	assert_eq!(scale.candle_time(2), 300);
+	assert_eq!(scale.timeframe(), 420);
+	assert_eq!(scale.chart_time(1200), Some(200));
+	assert!(scale.breaks().is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn compressed_time_scale_test() {
+	let scale = TimeScale::new(&candles_at(&[1000, 1060, 1300, 1360]), 60, TimeAxis::Compressed);
+
+	assert_eq!(scale.candle_time(2), 120);
+	assert_eq!(scale.timeframe(), 240);
+	assert_eq!(scale.chart_time(1030), Some(30));
+	// Within the gap, snaps to the next candle
+	assert_eq!(scale.chart_time(1200), Some(120));
+	assert_eq!(scale.chart_time(1420), Some(240));
+	assert_eq!(scale.chart_time(1421), None);
+	assert_eq!(scale.breaks(), &[2]);
+}
+
+#[cfg(test)]
+#[test]
+fn time_scale_without_timestamps_test() {
+	let mut candles = candles_at(&[1000, 1060, 1300]);
+	candles[1].t = None;
+
+	let scale = TimeScale::new(&candles, 60, TimeAxis::Continuous);
+
+	assert_eq!(scale.candle_time(2), 120);
+	assert_eq!(scale.start_time(), None);
+	assert_eq!(scale.chart_time(1000), None);
+}
+
+#[cfg(test)]
+#[test]
+fn empty_time_scale_test() {
+	let scale = TimeScale::new(&candles_at(&[]), 60, TimeAxis::Compressed);
+
+	assert_eq!(scale.timeframe(), 60);
+	assert_eq!(scale.start_time(), None);
+	assert!(scale.breaks().is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn sequential_time_scale_test() {
+	let scale = TimeScale::new(&candles_at(&[1000, 1000, 1030, 1300]), 60, TimeAxis::Sequential);
+
+	assert_eq!(scale.candle_time(3), 180);
+	assert_eq!(scale.timeframe(), 240);
+	assert_eq!(scale.start_time(), Some(1000));
+	// The third candle lasts until the fourth starts at 1300
+	assert_eq!(scale.chart_time(1165), Some(150));
+	assert_eq!(scale.chart_time(1330), None);
+	assert_eq!(scale.label_span(), 300);
+	assert!(scale.breaks().is_empty());
+
+	// Repeated timestamps can't be placed by time
+	assert_eq!(TimeScale::new(&candles_at(&[1000, 1000]), 60, TimeAxis::Continuous).start_time(), None);
+}
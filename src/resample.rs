@@ -0,0 +1,96 @@
+use data::Candle;
+use utils::{aggregate, SetAggregate};
+
+/// How candles are grouped into larger candles
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Resampling {
+	/// Every so many consecutive candles become one, the last candle may cover fewer
+	Count(usize),
+	/// Candles become one per window of the length in seconds, windows are aligned to multiples of the length since
+	/// the unix epoch. Candles without a timestamp join the window of the candle before them.
+	Window(i64),
+}
+
+/// Groups the candles into larger candles, e.g. 1-minute candles into 15-minute candles.
+/// Candles resampled by windows carry the timestamp of the start of their window.
+pub fn resample<C: Candle, D: From<SetAggregate>>(data: &[C], resampling: Resampling) -> Vec<D> {
+	match resampling {
+		Resampling::Count(count) => data.chunks(count.max(1)).map(|chunk| aggregate(chunk).into()).collect(),
+		Resampling::Window(length) => {
+			let length = length.max(1);
+			let mut candles = vec![];
+			let mut start = 0;
+			let mut window = None;
+
+			for (i, candle) in data.iter().enumerate() {
+				let candle_window = candle.timestamp().map(|t| t - t.rem_euclid(length)).or(window);
+
+				if candle_window != window && i > start {
+					candles.push(window_aggregate(&data[start..i], window));
+					start = i;
+				}
+
+				window = candle_window;
+			}
+
+			if start < data.len() {
+				candles.push(window_aggregate(&data[start..], window));
+			}
+
+			candles
+		}
+	}
+}
+
+fn window_aggregate<C: Candle, D: From<SetAggregate>>(data: &[C], window: Option<i64>) -> D {
+	let mut set = aggregate(data);
+	set.start = window.or(set.start);
+
+	set.into()
+}
+
+#[cfg(test)]
+fn minutes(from: i64, count: usize) -> Vec<::model::data::OHLC> {
+	(0..count)
+		.map(|i| {
+			let price = 100. + i as f64;
+			::model::data::OHLC { o: price, h: price + 2., l: price - 1., c: price + 1., t: Some(from + 60 * i as i64) }
+		})
+		.collect()
+}
+
+#[cfg(test)]
+#[test]
+fn resample_by_count_test() {
+	use model::data::OHLC;
+
+	let candles: Vec<OHLC> = resample(&minutes(0, 5), Resampling::Count(2));
+
+	assert_eq!(candles, vec![
+		OHLC { o: 100., h: 103., l: 99., c: 102., t: Some(0) },
+		OHLC { o: 102., h: 105., l: 101., c: 104., t: Some(120) },
+		OHLC { o: 104., h: 106., l: 103., c: 105., t: Some(240) },
+	]);
+}
+
+#[cfg(test)]
+#[test]
+fn resample_by_window_test() {
+	// 00:10 to 00:39, into the 00:00, 00:15 and 00:30 windows
+	let candles: Vec<SetAggregate> = resample(&minutes(600, 30), Resampling::Window(900));
+
+	assert_eq!(candles.iter().map(|set| (set.start, set.count)).collect::<Vec<_>>(), vec![(Some(0), 5), (Some(900), 15), (Some(1800), 10)]);
+	assert_eq!((candles[1].o, candles[1].h, candles[1].l, candles[1].c), (105., 121., 104., 120.));
+}
+
+#[cfg(test)]
+#[test]
+fn resample_by_window_with_gaps_test() {
+	let mut data = minutes(0, 3);
+	data.extend(minutes(3600, 2));
+	data[1].t = None;
+
+	let candles: Vec<SetAggregate> = resample(&data, Resampling::Window(600));
+
+	assert_eq!(candles.iter().map(|set| (set.start, set.count)).collect::<Vec<_>>(), vec![(Some(0), 3), (Some(3600), 2)]);
+}
@@ -1,9 +1,10 @@
 extern crate env_logger;
 extern crate serde_json;
 
-use serde::export::PhantomData;
+use std::collections::HashSet;
+use std::marker::PhantomData;
 
-use model::data::OHLC;
+use model::data::{OHLC, OHLCV};
 use model::rex::*;
 use model::rex::test_fill::TestFill;
 use model::rex::test_line::TestLine;
@@ -12,6 +13,10 @@ use model::rex::test_text::TestText;
 use super::*;
 
 fn draw_with_extension<T: RendererExtension<Candle=OHLC> + 'static>(ext: Option<T>, suffix: &str) {
+	draw_with_extension_antialiased(ext, suffix, false);
+}
+
+fn draw_with_extension_antialiased<T: RendererExtension<Candle=OHLC> + 'static>(ext: Option<T>, suffix: &str, antialias: bool) {
 	let _ = env_logger::try_init();
 
 	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
@@ -20,7 +25,8 @@ fn draw_with_extension<T: RendererExtension<Candle=OHLC> + 'static>(ext: Option<
 		let mut options = OHLCRenderOptions::new();
 		options.title(&format!("BTCUSD | ohlc-rs{}", suffix), 0x007F7FFF)
 			.line(0xCCCCCCFF, 200., 24)
-			.background_colour(0x36393EFF);
+			.background_colour(0x36393EFF)
+			.antialias(antialias);
 
 		if let Some(ext) = ext {
 			options.add_extension(ext);
@@ -28,11 +34,37 @@ fn draw_with_extension<T: RendererExtension<Candle=OHLC> + 'static>(ext: Option<
 
 		options.render_and_save(
 			data.clone(),
-			&Path::new(&format!("test-draw-sample-data{}.png", suffix)),
+			Path::new(&format!("test-draw-sample-data{}.png", suffix)),
 		).unwrap();
 	}
 }
 
+fn sample_data() -> Vec<OHLC> {
+	self::serde_json::from_str(include_str!("../sample_data.json")).unwrap()
+}
+
+/// Renders the candles with the options the function sets up, saves the image as `test-draw{name}.png` to look at and
+/// returns its pixels
+fn draw<F>(data: Vec<OHLC>, name: &str, setup: F) -> RgbBuffer where F: FnOnce(&mut OHLCRenderOptions<OHLC>) {
+	let _ = env_logger::try_init();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title(&format!("BTCUSD | ohlc-rs{}", name), 0x007F7FFF);
+	setup(&mut options);
+
+	let image = options.render_to_rgb_buffer(data).unwrap();
+	image::save_buffer(format!("test-draw{}.png", name), &image.buffer[..], image.width as u32, image.height as u32, image::RGB(8)).unwrap();
+
+	image
+}
+
+/// Colour of the pixel as 0xRRGGBB
+fn pixel(image: &RgbBuffer, x: usize, y: usize) -> u32 {
+	let i = (y * image.width + x) * 3;
+
+	(image.buffer[i] as u32) << 16 | (image.buffer[i + 1] as u32) << 8 | image.buffer[i + 2] as u32
+}
+
 #[test]
 fn render_draw_sample_data() {
 	draw_with_extension::<NoExtension<OHLC>>(None, "");
@@ -65,7 +97,7 @@ fn render_draw_sample_data_plus_rsi() {
 
 #[test]
 fn render_draw_sample_data_plus_volume() {
-	draw_with_extension(Some(Volume::new(0xCCCCCCFF, 0x27A819FF, 0xD33040FF)), "+volume");
+	draw_with_extension(Some(Volume::new(0xCCCCCCFF, 0x27A819FF, 0xD33040FF, 0xCCCCCC7F)), "+volume");
 }
 
 #[test]
@@ -87,3 +119,925 @@ fn render_draw_sample_data_with_test_fill_alpha() {
 fn render_draw_sample_data_with_test_line() {
 	draw_with_extension(Some(TestLine(PhantomData)), "_with_test_line");
 }
+
+#[test]
+fn render_draw_sample_data_thumbnail() {
+	let image = draw(sample_data(), "-sample-data_thumbnail", |options| {
+		options.line(0xCCCCCCFF, 200., 24)
+			.background_colour(0x36393EFF)
+			.dimensions(400, 200)
+			.margin(20, 20, 4, 60)
+			.add_extension(BollingerBands::new(20, 2, 0xFF0000FF))
+			.add_extension(RSI::new(0xCCCCCCFF, 0xFFFF007F, 0x27A819FF, 0xD33040FF))
+			.add_extension(TestLine(PhantomData));
+	});
+
+	// The RSI strip goes underneath, the test lines frame the whole image and the left margin stays empty
+	assert_eq!((image.width, image.height), (400, 200 + 175));
+	assert_eq!((pixel(&image, 200, 374), pixel(&image, 399, 100)), (0xFFFF00, 0xFFFF00));
+	assert_eq!(pixel(&image, 2, 100), 0x36393E);
+}
+
+#[test]
+fn render_draw_sample_data_4k() {
+	let image = draw(sample_data(), "-sample-data_4k", |options| {
+		options.line(0xCCCCCCFF, 200., 24)
+			.background_colour(0x36393EFF)
+			.dimensions(3840, 2160)
+			.margin(120, 70, 24, 226)
+			.add_extension(BollingerBands::new(20, 2, 0xFF0000FF))
+			.add_extension(RSI::new(0xCCCCCCFF, 0xFFFF007F, 0x27A819FF, 0xD33040FF))
+			.add_extension(TestLine(PhantomData));
+	});
+
+	assert_eq!((image.width, image.height), (3840, 2160 + 175));
+	assert_eq!((pixel(&image, 1920, 2334), pixel(&image, 3839, 1000)), (0xFFFF00, 0xFFFF00));
+	assert_eq!(pixel(&image, 12, 1000), 0x36393E);
+}
+
+#[test]
+fn options_round_trip_through_json() {
+	// Configurations saved before dimensions, margins and the later options existed
+	let baseline = r#"{
+		"title": "BTCUSD", "title_colour": 8323071, "background_colour": 909721343, "current_value_colour": 776268543,
+		"time_units": 3600, "line_colour": 3435973887, "price_line_interval": 200.0, "time_line_interval": 24,
+		"down_colour": 3543154943, "up_colour": 665327871
+	}"#;
+	let options: OHLCRenderOptions<OHLC> = self::serde_json::from_str(baseline).unwrap();
+	let defaults = OHLCRenderOptions::<OHLC>::new();
+
+	assert_eq!(options.title, "BTCUSD");
+	assert_eq!(options.price_line_interval, 200.);
+	assert_eq!((options.width, options.height), (defaults.width, defaults.height));
+	assert_eq!(options.margin, defaults.margin);
+	assert_eq!(options.theme, defaults.theme);
+
+	let mut options = OHLCRenderOptions::<OHLC>::new();
+	options.title("BTCUSD", 0x007F7FFF).dimensions(400, 200).margin(20, 20, 4, 60).theme(Theme::dark());
+	let json = self::serde_json::to_string(&options).unwrap();
+	let parsed: OHLCRenderOptions<OHLC> = self::serde_json::from_str(&json).unwrap();
+
+	assert_eq!(self::serde_json::to_string(&parsed).unwrap(), json);
+}
+
+#[test]
+fn render_rejects_oversized_margins() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.dimensions(100, 100).margin(60, 50, 0, 0);
+
+	match options.render_and_save(data, Path::new("test-draw-never-written.png")) {
+		Err(OhlcError::Layout(_)) => {}
+		result => panic!("expected a layout error, got {:?}", result),
+	}
+}
+
+#[test]
+fn render_reports_invalid_candles() {
+	let mut data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+	data[3].c = data[3].h + 1.;
+
+	match OHLCRenderOptions::new().render_to_rgb_buffer(data) {
+		Err(OhlcError::Validation { index: 3, field: CandleField::Close, .. }) => {}
+		result => panic!("expected a validation error, got {:?}", result.map(|_| ())),
+	}
+}
+
+#[test]
+fn render_with_validation_policies() {
+	let mut data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+	data[3].c = data[3].h + 1.;
+	data[5].o = f64::NAN;
+
+	let mut options = OHLCRenderOptions::new();
+
+	options.validation_policy(ValidationPolicy::Skip);
+	assert!(options.render_to_svg(data.clone()).is_ok());
+
+	options.validation_policy(ValidationPolicy::Repair);
+	assert!(options.render_to_svg(data.clone()).is_ok());
+
+	data.truncate(6);
+	data[0].l = f64::INFINITY;
+	data[1].h = -1.;
+	data[2].l = f64::NAN;
+	data[3].o = f64::NAN;
+	data[4].h = f64::NAN;
+
+	// Nothing is left to draw
+	assert!(options.render_to_svg(data).unwrap().contains("No data"));
+}
+
+#[test]
+fn render_reports_unsupported_formats() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let path = Path::new("test-draw-unsupported.gif");
+	let _ = std::fs::remove_file(path);
+
+	match OHLCRenderOptions::new().render_and_save(data, path) {
+		Err(OhlcError::Encoding(_)) => {}
+		result => panic!("expected an encoding error, got {:?}", result),
+	}
+	assert!(!path.exists());
+}
+
+#[test]
+fn render_to_rgb_buffer_includes_strips() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.dimensions(640, 320)
+		.add_extension(RSI::new(0xCCCCCCFF, 0xFFFF007F, 0x27A819FF, 0xD33040FF));
+
+	let image = options.render_to_rgb_buffer(data).unwrap();
+
+	assert_eq!(image.width, 640);
+	assert_eq!(image.height, 320 + 175);
+	assert_eq!(image.buffer.len(), image.width * image.height * 3);
+}
+
+#[test]
+fn render_to_png_bytes_is_png() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let bytes = OHLCRenderOptions::new().render_to_png_bytes(data).unwrap();
+
+	assert_eq!(&bytes[..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+}
+
+/// Backend that only counts what's drawn onto it
+struct CountingSurface {
+	width: usize,
+	height: usize,
+	font: Font,
+	lines: usize,
+	texts: Vec<String>,
+}
+
+impl DrawingSurface for CountingSurface {
+	fn width(&self) -> usize {
+		self.width
+	}
+
+	fn height(&self) -> usize {
+		self.height
+	}
+
+	fn font(&self) -> &Font {
+		&self.font
+	}
+
+	fn set_font(&mut self, font: Font) {
+		self.font = font;
+	}
+
+	fn extend(&mut self, height: usize, _background: u32) {
+		self.height += height;
+	}
+
+	fn colour(&mut self, _x: usize, _y: usize, _rgba: u32) {}
+
+	fn rect(&mut self, _x1: usize, _y1: usize, _x2: usize, _y2: usize, _rgba: u32) {}
+
+	fn line(&mut self, _p1: Point, _p2: Point, _rgba: u32) {
+		self.lines += 1;
+	}
+
+	fn polyline(&mut self, points: &[Point], _rgba: u32, _stroke: &Stroke) {
+		self.lines += points.len().saturating_sub(1);
+	}
+
+	fn text(&mut self, _topleft: Point, text: &str, _rgba: u32) {
+		self.texts.push(text.to_string());
+	}
+}
+
+#[test]
+fn render_to_surface_of_another_backend() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD", 0x007F7FFF)
+		.dimensions(640, 320)
+		.add_extension(RSI::themed());
+
+	let surface = options.render_to_surface(data, |width, height, _| CountingSurface { width, height, font: Font::bitmap(), lines: 0, texts: vec![] }).unwrap();
+
+	assert_eq!((surface.width, surface.height), (640, 320 + 175));
+	assert!(surface.lines > 0);
+	assert!(surface.texts.iter().any(|text| text == "BTCUSD"));
+	assert!(surface.texts.iter().any(|text| text == "Overbought"));
+}
+
+#[test]
+fn render_draw_sample_data_svg() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs.svg", 0x007F7FFF)
+		.line(0xCCCCCCFF, 200., 24)
+		.background_colour(0x36393EFF)
+		.add_extension(BollingerBands::new(20, 2, 0xFF0000FF))
+		.add_extension(MACD::new(0xFF007FFF, 0xFFFFFFFF, 0x00FFFFFF, 0xFF0000FF, 0.1));
+
+	options.render_and_save(data, Path::new("test-draw-sample-data.svg")).unwrap();
+}
+
+#[test]
+fn render_to_svg_includes_strips() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("<BTC & USD>", 0x007F7FFF)
+		.add_extension(RSI::new(0xCCCCCCFF, 0xFFFF007F, 0x27A819FF, 0xD33040FF));
+
+	let svg = options.render_to_svg(data).unwrap();
+
+	assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="1310" height="825""#));
+	assert!(svg.contains(r##"<rect y="650" width="1310" height="175" fill="#DDDDDD"/>"##));
+	assert!(svg.contains("&lt;BTC &amp; USD&gt;"));
+	assert!(svg.ends_with("</svg>\n"));
+}
+
+#[test]
+fn render_draw_sample_data_with_timestamps() {
+	let _ = env_logger::try_init();
+
+	let mut data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+	// 2018-09-27 05:00:00 UTC onwards, hourly
+	for (i, candle) in data.iter_mut().enumerate() {
+		candle.t = Some(1538024400 + 3600 * i as i64);
+	}
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_timestamps", 0x007F7FFF)
+		.line(0xCCCCCCFF, 200., 24)
+		.absolute_time_labels("%a %d %b", 36000)
+		.background_colour(0x36393EFF);
+
+	options.render_and_save(data, Path::new("test-draw-sample-data_timestamps.png")).unwrap();
+}
+
+#[test]
+fn ohlc_timestamp_is_optional() {
+	let candle: OHLC = self::serde_json::from_str(r#"{"o": 1, "h": 2, "l": 0.5, "c": 1.5}"#).unwrap();
+	assert_eq!(candle.timestamp(), None);
+	assert_eq!(self::serde_json::to_string(&candle).unwrap(), r#"{"o":1.0,"h":2.0,"l":0.5,"c":1.5}"#);
+
+	let candle: OHLC = self::serde_json::from_str(r#"{"o": 1, "h": 2, "l": 0.5, "c": 1.5, "t": 1538024400}"#).unwrap();
+	assert_eq!(candle.timestamp(), Some(1538024400));
+}
+
+/// Hourly sample data from 2018-09-27 05:00:00 UTC onwards, with 2018-09-29 and 2018-09-30 missing
+fn sample_data_with_gaps() -> Vec<OHLC> {
+	sample_data().into_iter()
+		.enumerate()
+		.map(|(i, candle)| OHLC { t: Some(1538024400 + 3600 * i as i64), ..candle })
+		.filter(|candle| candle.t.unwrap() < 1538179200 || candle.t.unwrap() >= 1538352000)
+		.collect()
+}
+
+/// Pixels of the candle colours in the columns around x, between the top and bottom margins of the main chart
+fn candle_pixels_around(image: &RgbBuffer, x: usize) -> usize {
+	(x - 5..x + 5)
+		.flat_map(|x| (60..615).map(move |y| (x, y)))
+		.filter(|&(x, y)| pixel(image, x, y) == 0x27A819 || pixel(image, x, y) == 0xD33040)
+		.count()
+}
+
+#[test]
+fn render_draw_sample_data_with_gaps() {
+	let image = draw(sample_data_with_gaps(), "-sample-data_gaps", |options| {
+		options.line(0xCCCCCCFF, 200., 24)
+			.absolute_time_labels("%a %d %b", 0)
+			.background_colour(0x36393EFF)
+			.add_extension(EMA::new(20, 0.1, 0xFF0000FF))
+			.add_extension(Volume::new(0xCCCCCCFF, 0x27A819FF, 0xD33040FF, 0xCCCCCC7F));
+	});
+
+	// Noon on the 29th, in the middle of the week the chart covers, only has the lines of the high and the low
+	let x = 12 + (1185. * (1538222400 - 1538024400) as f64 / (7. * 86400.)) as usize;
+	assert!(candle_pixels_around(&image, x) <= 2 * 10);
+}
+
+#[test]
+fn render_draw_sample_data_with_compressed_gaps() {
+	let image = draw(sample_data_with_gaps(), "-sample-data_compressed_gaps", |options| {
+		options.line(0xCCCCCCFF, 200., 24)
+			.absolute_time_labels("%a %d %b", 0)
+			.time_axis(TimeAxis::Compressed)
+			.background_colour(0x36393EFF)
+			.add_extension(EMA::new(20, 0.1, 0xFF0000FF))
+			.add_extension(Volume::new(0xCCCCCCFF, 0x27A819FF, 0xD33040FF, 0xCCCCCC7F));
+	});
+
+	// The candles after the gap move in to where it was
+	let x = 12 + (1185. * (1538222400 - 1538024400) as f64 / (7. * 86400.)) as usize;
+	assert!(candle_pixels_around(&image, x) > 2 * 10);
+}
+
+#[test]
+fn render_draw_exponential_data_log_scale() {
+	let _ = env_logger::try_init();
+
+	// Grows 100x over 200 candles
+	let data: Vec<OHLC> = (0..200).map(|i| {
+		let o = 100. * 1.0233_f64.powi(i);
+		let c = o * if i % 3 == 0 { 0.98 } else { 1.04 };
+
+		OHLC { o, h: o.max(c) * 1.01, l: o.min(c) * 0.99, c, t: None }
+	}).collect();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("ohlc-rs_log_scale", 0x007F7FFF)
+		.line(0xCCCCCCFF, 1000., 24)
+		.price_scale(PriceScale::Logarithmic)
+		.background_colour(0x36393EFF);
+
+	options.render_and_save(data.clone(), Path::new("test-draw-exponential-data_log_scale.png")).unwrap();
+
+	options.price_scale(PriceScale::Linear);
+	options.render_and_save(data, Path::new("test-draw-exponential-data_linear_scale.png")).unwrap();
+}
+
+#[test]
+fn render_log_scale_rejects_non_positive_prices() {
+	let data = vec![OHLC { o: 1., h: 2., l: 0., c: 1., t: None }];
+
+	let mut options = OHLCRenderOptions::new();
+	options.price_scale(PriceScale::Logarithmic);
+
+	match options.render_to_rgb_buffer(data) {
+		Err(OhlcError::Validation { index: 0, field: CandleField::Low, .. }) => {}
+		result => panic!("expected a validation error, got {:?}", result.map(|_| ())),
+	}
+}
+
+#[test]
+fn render_draw_sub_cent_data_auto_lines() {
+	let _ = env_logger::try_init();
+
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+	// Scale BTC prices down to a $0.0003 token on 1 minute candles
+	let data = data.into_iter()
+		.map(|candle| OHLC { o: candle.o / 2e7, h: candle.h / 2e7, l: candle.l / 2e7, c: candle.c / 2e7, t: None })
+		.collect();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("ohlc-rs_auto_lines", 0x007F7FFF)
+		.auto_line(0xCCCCCCFF, 60, 120)
+		.time_units(60)
+		.background_colour(0x36393EFF);
+
+	options.render_and_save(data, Path::new("test-draw-sample-data_auto_lines.png")).unwrap();
+
+	// Charts keep their fixed intervals unless they opt in
+	assert!(OHLCRenderOptions::<OHLC>::new().auto_grid.is_none());
+}
+
+#[test]
+fn render_draw_sample_data_price_format() {
+	let _ = env_logger::try_init();
+
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_price_format", 0x007F7FFF)
+		.margin(60, 35, 12, 133)
+		.auto_line(0xFFFFFFAA, 50, 150)
+		.price_format(NumberFormat::new().decimals(2).thousands_separator(',').prefix("$"))
+		.background_colour(0x36393EFF);
+
+	options.render_and_save(data, Path::new("test-draw-sample-data_price_format.png")).unwrap();
+}
+
+#[test]
+fn render_draw_sample_data_plus_bb_antialiased() {
+	draw_with_extension_antialiased(Some(BollingerBands::new(20, 2, 0xFF0000FF)), "+bb_antialiased", true);
+}
+
+#[test]
+fn render_draw_sample_data_with_test_line_antialiased() {
+	draw_with_extension_antialiased(Some(TestLine(PhantomData)), "_with_test_line_antialiased", true);
+}
+
+/// Dashed grid lines at half opacity and wide, dashed and dotted indicator lines
+fn set_up_strokes(options: &mut OHLCRenderOptions<OHLC>, antialias: bool) {
+	options.line(0xCCCCCC7F, 200., 24)
+		.background_colour(0x36393EFF)
+		.grid_stroke(Stroke::new().dashed(6, 4))
+		.indicative_stroke(Stroke::new().dotted())
+		.antialias(antialias)
+		.add_extension(BollingerBands::new(20, 2, 0xFF0000FF).stroke(Stroke::new().width(2)))
+		.add_extension(EMA::new(20, 0.1, 0xFFFF00FF).stroke(Stroke::new().width(3).dashed(12, 6).cap(LineCap::Round)))
+		.add_extension(MACD::new(0x7F7FFFFF, 0xFF7F00FF, 0x7F7F7F7F, 0xCCCCCCFF, 0.1).stroke(Stroke::new().width(2)));
+}
+
+/// 0xCCCCCC at half opacity over the background
+const HALF_GRID: u32 = 0x818285;
+
+/// Rows of the main chart's price lines, with how many of their pixels are the grid colour and how many are blended
+/// between the background and the grid colour
+fn price_line_rows(image: &RgbBuffer) -> Vec<(usize, usize, usize)> {
+	let blended = |rgb: u32| (rgb >> 16) > 0x36 && (rgb >> 16) < 0x81 && (rgb >> 8 & 0xFF) > 0x39 && (rgb >> 8 & 0xFF) < 0x82 && (rgb & 0xFF) > 0x3E && (rgb & 0xFF) < 0x85;
+
+	(60..615)
+		.map(|y| (
+			y,
+			(12..1197).filter(|&x| pixel(image, x, y) == HALF_GRID).count(),
+			(12..1197).filter(|&x| blended(pixel(image, x, y))).count(),
+		))
+		.filter(|&(_, grid, blended)| grid + blended > 200)
+		.collect()
+}
+
+#[test]
+fn render_draw_sample_data_strokes() {
+	let image = draw(sample_data(), "-sample-data_strokes", |options| set_up_strokes(options, false));
+	let rows = price_line_rows(&image);
+
+	// The dashes leave gaps in the lines, which are drawn in whole pixels
+	assert!(rows.len() >= 10);
+	for (y, grid, blended) in rows {
+		assert!(grid > 1185 * 2 / 5 && grid < 1185 * 3 / 4, "{} pixels of the line at y = {} are the grid colour", grid, y);
+		assert_eq!(blended, 0);
+	}
+}
+
+#[test]
+fn render_draw_sample_data_strokes_antialiased() {
+	let image = draw(sample_data(), "-sample-data_strokes_antialiased", |options| set_up_strokes(options, true));
+	let rows = price_line_rows(&image);
+
+	// The ends of the dashes are blended into the background
+	assert!(rows.len() >= 10);
+	for (y, grid, blended) in rows {
+		assert!(grid < 1185 * 3 / 4, "{} pixels of the line at y = {} are the grid colour", grid, y);
+		assert!(blended > 100, "{} pixels of the line at y = {} are blended", blended, y);
+	}
+}
+
+#[test]
+fn render_to_svg_strokes() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.grid_stroke(Stroke::new().dashed(6, 4))
+		.add_extension(EMA::new(20, 0.1, 0xFFFF00FF).stroke(Stroke::new().width(2).dotted()));
+
+	let svg = options.render_to_svg(data).unwrap();
+
+	assert!(svg.contains(r#"stroke-width="1" stroke-linecap="butt" stroke-linejoin="round" stroke-dasharray="6 4""#));
+	assert!(svg.contains(r#"stroke-width="2" stroke-linecap="round" stroke-linejoin="round" stroke-dasharray="0 4""#));
+}
+
+#[test]
+fn render_draw_sample_data_truetype() {
+	let _ = env_logger::try_init();
+
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTC/EUR € | ohlc-rs_truetype", 0x007F7FFF)
+		.line(0xCCCCCCFF, 200., 24)
+		.background_colour(0x36393EFF)
+		.antialias(true)
+		.font(Font::embedded(16.))
+		.title_font_size(28.)
+		.add_extension(RSI::new(0xCCCCCCFF, 0xFFFF007F, 0x27A819FF, 0xD33040FF));
+
+	options.render_and_save(data.clone(), Path::new("test-draw-sample-data_truetype.png")).unwrap();
+
+	let svg = options.render_to_svg(data).unwrap();
+
+	assert!(svg.contains(r#"font-family="DejaVu Sans Mono, monospace""#));
+	assert!(svg.contains("BTC/EUR €"));
+}
+
+#[test]
+fn render_draw_sample_data_label_layout() {
+	let _ = env_logger::try_init();
+
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_label_layout", 0x007F7FFF)
+		.line(0xCCCCCCFF, 100., 24)
+		.background_colour(0x36393EFF)
+		.add_extension(EMA::new(20, 0.1, 0xFFFF00FF).value_label())
+		.add_extension(DEMA::new(EMA::new(20, 0.1, 0xFF7F00FF)).value_label());
+
+	options.render_and_save(data, Path::new("test-draw-sample-data_label_layout.png")).unwrap();
+}
+
+/// Renders the sample data with themed extensions and checks that the colours drawn are the theme's
+fn assert_draws_theme(theme: Theme, name: &str) {
+	// The theme overrides the colour of the title
+	let image = draw(sample_data(), name, |options| {
+		options.title(&format!("BTCUSD | ohlc-rs{}", name), 0)
+			.theme(theme.clone())
+			.add_extension(BollingerBands::themed(20, 2))
+			.add_extension(EMA::themed(20, 0.1).value_label())
+			.add_extension(RSI::themed())
+			.add_extension(MACD::themed(0.1));
+	});
+
+	let drawn: HashSet<u32> = (0..image.height)
+		.flat_map(|y| (0..image.width).map(move |x| (x, y)))
+		.map(|(x, y)| pixel(&image, x, y))
+		.collect();
+
+	assert_eq!(pixel(&image, 2, 300), theme.background >> 8);
+
+	// The MACD takes the 4th and 5th indicator colours
+	for rgba in [theme.up, theme.down, theme.current_value].iter().cloned().chain((0..5).map(|i| theme.indicator(i))) {
+		assert!(drawn.contains(&(rgba >> 8)), "{:08X} of the theme isn't drawn", rgba);
+	}
+}
+
+#[test]
+fn render_draw_sample_data_theme_dark() {
+	assert_draws_theme(Theme::dark(), "-sample-data_theme_dark");
+}
+
+#[test]
+fn render_draw_sample_data_theme_light() {
+	assert_draws_theme(Theme::light(), "-sample-data_theme_light");
+}
+
+#[test]
+fn render_draw_sample_data_theme_high_contrast() {
+	assert_draws_theme(Theme::high_contrast(), "-sample-data_theme_high_contrast");
+}
+
+#[test]
+fn render_draw_sample_data_theme_colour_blind_safe() {
+	assert_draws_theme(Theme::colour_blind_safe(), "-sample-data_theme_colour_blind_safe");
+}
+
+#[test]
+fn theme_round_trips_through_json() {
+	let theme = Theme::colour_blind_safe();
+	let json = self::serde_json::to_string(&theme).unwrap();
+
+	assert_eq!(self::serde_json::from_str::<Theme>(&json).unwrap(), theme);
+}
+
+fn set_up_every_extension(options: &mut OHLCRenderOptions<OHLC>) {
+	options.add_extension(BollingerBands::themed(20, 2))
+		.add_extension(EMA::themed(20, 0.1).value_label())
+		.add_extension(DEMA::new(EMA::themed(20, 0.1)))
+		.add_extension(RSI::themed())
+		.add_extension(MACD::themed(0.1))
+		.add_extension(Volume::themed());
+}
+
+/// Draws the candles with every extension, checking that the SVG backend copes with them as well
+fn draw_edge_case(data: Vec<OHLC>, name: &str) -> RgbBuffer {
+	let mut options = OHLCRenderOptions::new();
+	set_up_every_extension(&mut options);
+	assert!(options.render_to_svg(data.clone()).is_ok());
+
+	draw(data, name, set_up_every_extension)
+}
+
+/// Number of pixels in the main chart drawn in the up or down candle colour
+fn candle_pixels(image: &RgbBuffer) -> usize {
+	(60..615).flat_map(|y| (12..1197).map(move |x| (x, y)))
+		.filter(|&(x, y)| [0x27A819, 0xD33040].contains(&pixel(image, x, y)))
+		.count()
+}
+
+#[test]
+fn render_draw_empty_data() {
+	let image = draw_edge_case(vec![], "-empty_data");
+
+	assert_eq!((image.width, image.height), (1310, 650 + 175 + 135 + 175));
+	assert_eq!(candle_pixels(&image), 0);
+
+	// The "No data" placeholder is written in the middle of the main chart
+	let placeholder = (320..355).flat_map(|y| (560..650).map(move |x| (x, y)))
+		.filter(|&(x, y)| pixel(&image, x, y) == 0x000000)
+		.count();
+	assert!(placeholder > 0);
+
+	let svg = OHLCRenderOptions::<OHLC>::new().render_to_svg(vec![]).unwrap();
+	assert!(svg.contains("No data"));
+}
+
+#[test]
+fn render_rejects_empty_data_when_asked() {
+	let invalid = vec![OHLC { o: 7600., h: 7500., l: 7580., c: 7630., t: None }];
+
+	let mut options = OHLCRenderOptions::<OHLC>::new();
+	options.reject_empty_data(true);
+
+	assert!(matches!(options.render_to_rgb_buffer(vec![]), Err(OhlcError::EmptyData)));
+	assert!(matches!(options.render_to_svg(vec![]), Err(OhlcError::EmptyData)));
+
+	// Nothing is left once the invalid candles are skipped
+	options.validation_policy(ValidationPolicy::Skip);
+	assert!(matches!(options.render_to_rgb_buffer(invalid), Err(OhlcError::EmptyData)));
+}
+
+#[test]
+fn render_draw_single_candle() {
+	let image = draw_edge_case(vec![OHLC { o: 7600., h: 7650., l: 7580., c: 7630., t: None }], "-single_candle");
+
+	assert!(candle_pixels(&image) > 0);
+}
+
+#[test]
+fn render_draw_flat_data() {
+	let image = draw_edge_case(vec![OHLC { o: 7600., h: 7600., l: 7600., c: 7600., t: None }; 30], "-flat_data");
+
+	// The candles and the indicators collapse onto a single row halfway down the padded price range
+	assert!((12..1197).all(|x| ![0xDDDDDD, 0xF4F4F4].contains(&pixel(&image, x, 337))));
+	assert!((12..1197).all(|x| [0xDDDDDD, 0xF4F4F4, 0xFBFBFB].contains(&pixel(&image, x, 330))));
+}
+
+#[test]
+fn render_draw_short_data() {
+	let image = draw_edge_case(sample_data()[..5].to_vec(), "-short_data");
+
+	assert!(candle_pixels(&image) > 0);
+}
+
+#[test]
+fn render_draw_sample_data_price_padding() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_price_padding", 0)
+		.price_padding(PricePadding::Percent(5.), PricePadding::Absolute(100.))
+		.include_indicators_in_range(true)
+		.add_extension(BollingerBands::themed(20, 2));
+
+	options.render_and_save(data.clone(), Path::new("test-draw-sample-data_price_padding.png")).unwrap();
+
+	let (max_price, min_price) = options.price_range(&data);
+	let aggregate = aggregate(&data);
+	let (_, highest_band) = BollingerBands::<OHLC>::themed(20, 2).price_range(&data).unwrap();
+
+	// The upper band rises above the highest price
+	assert!(highest_band > aggregate.h);
+	assert!((min_price - (aggregate.l - 100.)).abs() < 1e-9);
+	assert!((max_price - (highest_band + (highest_band - aggregate.l) * 0.05)).abs() < 1e-9);
+}
+
+#[test]
+fn render_draw_sample_data_fixed_price_range() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_fixed_price_range", 0)
+		.fixed_price_range(6000., 7500.);
+
+	options.render_and_save(data.clone(), Path::new("test-draw-sample-data_fixed_price_range.png")).unwrap();
+	assert_eq!(options.price_range(&data), (7500., 6000.));
+
+	options.fixed_price_range(7500., 6000.);
+
+	match options.render_to_svg(data) {
+		Err(OhlcError::Layout(_)) => {}
+		result => panic!("expected a layout error, got {:?}", result),
+	}
+}
+
+#[test]
+fn render_draw_sample_data_resampled() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_resampled 4h", 0)
+		.add_extension(BollingerBands::themed(20, 2));
+
+	let count = options.render_resampled(&data, 4 * 3600, |options, candles| {
+		assert_eq!(options.time_units, 4 * 3600);
+		options.render_and_save(candles.clone(), Path::new("test-draw-sample-data_resampled.png")).unwrap();
+
+		candles.len()
+	});
+
+	assert_eq!(count, data.len().div_ceil(4));
+	assert_eq!(options.time_units, 3600);
+}
+
+#[test]
+fn render_resampled_by_timestamps() {
+	// Hourly candles from 2018-09-27 00:30 UTC
+	let data = (0..12)
+		.map(|i| OHLC { o: 100., h: 101., l: 99., c: 100., t: Some(1538008200 + 3600 * i) })
+		.collect::<Vec<_>>();
+
+	let candles = OHLCRenderOptions::new().render_resampled(&data, 4 * 3600, |_, candles| candles);
+
+	// Candles from 00:30 to 11:30 fall into the windows starting at 00:00, 04:00 and 08:00
+	assert_eq!(candles.iter().map(|candle| candle.t.unwrap() - 1538006400).collect::<Vec<_>>(), vec![0, 4 * 3600, 8 * 3600]);
+}
+
+/// A day of trades, drifting up with some noise
+fn generated_ticks(trades: i64, seconds_apart: f64) -> Vec<Tick> {
+	let mut seed: u64 = 42;
+	let mut random = || {
+		seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+		(seed >> 33) as f64 / (1u64 << 31) as f64
+	};
+
+	let mut price = 6500.;
+
+	(0..trades)
+		.map(|i| {
+			price += (random() - 0.48) * 10.;
+			let side = if random() < 0.5 { Side::Buy } else { Side::Sell };
+
+			Tick { price, size: random() * 2., side, timestamp: 1538006400 + (i as f64 * seconds_apart) as i64 }
+		})
+		.collect()
+}
+
+#[test]
+fn render_draw_candles_from_ticks() {
+	let candles: Vec<SetAggregate> = candles_from_ticks(&generated_ticks(2880, 30.), Bars::Time(1800));
+	assert_eq!(candles.len(), 48);
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_ticks", 0)
+		.bars(Bars::Time(1800))
+		.add_extension(Volume::themed());
+
+	assert_eq!(options.time_units, 1800);
+	options.render_and_save(candles, Path::new("test-draw-ticks.png")).unwrap();
+}
+
+#[test]
+fn render_draw_candles_from_ticks_activity_bars() {
+	// Several trades a second, so bars may start at the same second
+	let ticks = generated_ticks(6000, 0.4);
+
+	for &(bars, suffix) in &[(Bars::TickCount(100), "tick_count"), (Bars::Volume(100.), "volume"), (Bars::DollarValue(650_000.), "dollar_value")] {
+		let candles: Vec<SetAggregate> = candles_from_ticks(&ticks, bars);
+
+		let mut options = OHLCRenderOptions::new();
+		options.title(&format!("BTCUSD | ohlc-rs_ticks_{}", suffix), 0)
+			.bars(bars)
+			.add_extension(Volume::themed());
+
+		// Bars sit next to each other however long they took, with the axis still labelled by their times
+		let time_scale = TimeScale::new(&candles, options.time_units as i64, options.time_axis);
+		assert_eq!(time_scale.candle_time(candles.len() - 1), (candles.len() as i64 - 1) * options.time_units as i64);
+		assert_eq!(time_scale.start_time(), Some(1538006400));
+		assert!(time_scale.breaks().is_empty());
+		assert_eq!(options.time_labels, TimeLabels::Absolute { format: "%H:%M".to_string(), utc_offset: 0 });
+
+		options.render_and_save(candles, Path::new(&format!("test-draw-ticks_{}.png", suffix))).unwrap();
+	}
+}
+
+#[test]
+fn ohlcv_reads_exchange_shapes() {
+	let short: OHLCV = self::serde_json::from_str(r#"{"t": 1538006400, "o": 1, "h": 2, "l": 0.5, "c": 1.5, "v": 10, "bv": 4}"#).unwrap();
+	let long: OHLCV = self::serde_json::from_str(r#"{"time": "1538006400", "open": "1", "high": "2", "low": "0.5", "close": "1.5", "volume": "10", "taker_buy_volume": "4"}"#).unwrap();
+
+	assert_eq!(short, OHLCV { t: Some(1538006400), o: 1., h: 2., l: 0.5, c: 1.5, v: 10., bv: Some(4.) });
+	assert_eq!(long, short);
+	assert_eq!(short.sell_volume(), Some(6.));
+
+	// Milliseconds and ISO 8601 dates, as CSV timestamps are read
+	let binance: OHLCV = self::serde_json::from_str(r#"{"open_time": 1538006400000, "o": "1", "h": "2", "l": "0.5", "c": "1.5"}"#).unwrap();
+	let iso: OHLCV = self::serde_json::from_str(r#"{"time": "2018-09-27T00:00:00Z", "o": 1, "h": 2, "l": 0.5, "c": 1.5}"#).unwrap();
+	assert_eq!((binance.t, iso.t), (Some(1538006400), Some(1538006400)));
+	assert!(self::serde_json::from_str::<OHLCV>(r#"{"t": "yesterday", "o": 1, "h": 2, "l": 0.5, "c": 1.5}"#).is_err());
+
+	let bare: OHLCV = self::serde_json::from_str(r#"{"Open": 1, "High": 2, "Low": 0.5, "Close": 1.5}"#).unwrap();
+	assert_eq!((bare.t, bare.v, bare.bv), (None, 0., None));
+	assert!(self::serde_json::from_str::<OHLCV>(r#"{"o": "one", "h": 2, "l": 0.5, "c": 1.5}"#).is_err());
+
+	// Round trips through its short names
+	assert_eq!(self::serde_json::from_str::<OHLCV>(&self::serde_json::to_string(&short).unwrap()).unwrap(), short);
+}
+
+#[test]
+fn render_draw_sample_data_ohlcv() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+	let data = data.into_iter()
+		.enumerate()
+		.map(|(i, ohlc)| {
+			let v = ohlc.range() * (1. + (i % 7) as f64);
+			let bv = if ohlc.c >= ohlc.o { v * 0.7 } else { v * 0.3 };
+
+			OHLCV { v, bv: Some(bv), ..OHLCV::from(ohlc) }
+		})
+		.collect::<Vec<_>>();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_ohlcv", 0)
+		.add_extension(Volume::themed());
+
+	options.render_and_save(data, Path::new("test-draw-sample-data_ohlcv.png")).unwrap();
+}
+
+#[test]
+fn render_draw_sample_data_heikin_ashi() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.title("BTCUSD | ohlc-rs_heikin_ashi", 0)
+		.heikin_ashi(true);
+
+	// The synthetic candles only change what's drawn, the chart still fits and marks the real prices
+	let heikin_ashi: Vec<OHLC> = heikin_ashi(&data);
+	let ha_high = heikin_ashi.iter().fold(f64::MIN, |high, candle| high.max(candle.h));
+	let ha_low = heikin_ashi.iter().fold(f64::MAX, |low, candle| low.min(candle.l));
+	assert_eq!(options.price_range(&data), (ha_high, ha_low));
+
+	let svg = options.render_to_svg(data.clone()).unwrap();
+	let last_close = options.price_format.format(data.last().unwrap().c);
+	assert!(svg.contains(&format!(">{}<", last_close)));
+
+	options.render_and_save(data, Path::new("test-draw-sample-data_heikin_ashi.png")).unwrap();
+}
+
+#[test]
+fn render_candle_styles() {
+	let rising = vec![OHLC { o: 1., h: 4., l: 0., c: 3., t: None }];
+	let pixel = |style: CandleStyle| {
+		let mut options = OHLCRenderOptions::new();
+		options.margin(10, 10, 10, 10)
+			.candle_style(style);
+
+		// Inside the body, away from the wick and the grid lines
+		let image = options.render_to_rgb_buffer(rising.clone()).unwrap();
+		let (x, y) = (10 + (image.width - 20) / 5, 10 + (image.height - 20) * 7 / 16);
+		let i = (y * image.width + x) * 3;
+
+		(image.buffer[i..i + 3].to_vec(), options.up_colour, options.background_colour)
+	};
+
+	let rgb = |rgba: u32| vec![(rgba >> 24) as u8, (rgba >> 16) as u8, (rgba >> 8) as u8];
+
+	let (filled, up, background) = pixel(CandleStyle::Filled);
+	assert_eq!(filled, rgb(up));
+
+	let (hollow, _, _) = pixel(CandleStyle::Hollow);
+	assert_eq!(hollow, rgb(background));
+
+	// Bars only have ticks at the open and close
+	let (bars, _, _) = pixel(CandleStyle::Bars);
+	assert_eq!(bars, rgb(background));
+}
+
+#[test]
+fn render_draw_sample_data_candle_styles() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+	// Fewer candles so the ticks and hollow bodies can be told apart
+	let data = data[data.len() - 60..].to_vec();
+
+	for &(style, suffix) in &[(CandleStyle::Bars, "bars"), (CandleStyle::Hollow, "hollow")] {
+		let mut options = OHLCRenderOptions::new();
+		options.title(&format!("BTCUSD | ohlc-rs_{}", suffix), 0)
+			.candle_style(style);
+
+		options.render_and_save(data.clone(), Path::new(&format!("test-draw-sample-data_{}.png", suffix))).unwrap();
+	}
+}
+
+#[test]
+fn render_to_svg_edges() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+
+	let mut options = OHLCRenderOptions::new();
+	options.add_extension(EMA::new(20, 0.1, 0xFFFF00FF));
+
+	// Pixel-exact PNGs leave SVG lines to the viewer's anti-aliasing
+	let svg = options.render_to_svg(data.clone()).unwrap();
+	assert!(svg.lines().filter(|line| line.starts_with("<line")).all(|line| !line.contains("crispEdges")));
+
+	options.svg_crisp_edges(true);
+	let svg = options.render_to_svg(data).unwrap();
+	assert!(svg.lines().filter(|line| line.starts_with("<line")).all(|line| line.contains("crispEdges")));
+}
+
+#[test]
+fn render_themed_extensions_after_macd() {
+	let data: Vec<OHLC> = self::serde_json::from_str(include_str!("../sample_data.json")).unwrap();
+	let theme = Theme::light();
+	let ema = EMA::themed(20, 0.1);
+	let legend = format!(">{}</text>", ema.name());
+
+	let mut options = OHLCRenderOptions::new();
+	options.theme(theme.clone())
+		.add_extension(MACD::themed(0.1))
+		.add_extension(ema);
+
+	// The MACD takes the first 2 colours for its lines, so the EMA gets the third rather than the signal line's
+	let svg = options.render_to_svg(data).unwrap();
+	let legend_line = svg.lines().find(|line| line.contains(&legend)).unwrap();
+
+	assert!(legend_line.contains(&format!("fill=\"#{:06X}\"", theme.indicator(2) >> 8)));
+}
+
+
+
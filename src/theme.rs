@@ -0,0 +1,102 @@
+/// Colours of a chart, extensions created without colours take theirs from here
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Theme {
+	pub background: u32,
+	/// Title and labels of extension strips
+	pub text: u32,
+	/// Grid lines and axis labels
+	pub grid: u32,
+	/// Candles that closed higher than they opened, buy volume
+	pub up: u32,
+	/// Candles that closed lower than they opened, sell volume
+	pub down: u32,
+	/// The current value line and label
+	pub current_value: u32,
+	/// Background of the legend entries
+	pub legend_background: u32,
+	/// Colours of indicators, handed out in order of the extensions
+	pub indicators: Vec<u32>,
+}
+
+impl Default for Theme {
+	fn default() -> Theme {
+		Theme::light()
+	}
+}
+
+impl Theme {
+	/// Light grey background with white grid lines, the colours charts have always had
+	pub fn light() -> Theme {
+		Theme {
+			background: 0xDDDDDDFF,
+			text: 0x000000FF,
+			grid: 0xFFFFFFAA,
+			up: 0x27A819FF,
+			down: 0xD33040FF,
+			current_value: 0x2E44EAFF,
+			legend_background: 0x7F7F7F7F,
+			indicators: vec![0xE07B00FF, 0x8E24AAFF, 0x00838FFF, 0xC2185BFF, 0x5D4037FF],
+		}
+	}
+
+	pub fn dark() -> Theme {
+		Theme {
+			background: 0x36393EFF,
+			text: 0xCCCCCCFF,
+			grid: 0xCCCCCC55,
+			up: 0x27A819FF,
+			down: 0xD33040FF,
+			current_value: 0x4C8DFFFF,
+			legend_background: 0x0000007F,
+			indicators: vec![0xFFB000FF, 0x00B4D8FF, 0xE056FDFF, 0x7CFC00FF, 0xFF6F61FF],
+		}
+	}
+
+	/// Saturated colours on black
+	pub fn high_contrast() -> Theme {
+		Theme {
+			background: 0x000000FF,
+			text: 0xFFFFFFFF,
+			grid: 0xFFFFFF66,
+			up: 0x00FF00FF,
+			down: 0xFF0000FF,
+			current_value: 0xFFFF00FF,
+			legend_background: 0x000000CC,
+			indicators: vec![0x00FFFFFF, 0xFF00FFFF, 0xFFFFFFFF, 0xFF8000FF],
+		}
+	}
+
+	/// Okabe-Ito colours on white, rises and falls are blue and orange rather than green and red
+	pub fn colour_blind_safe() -> Theme {
+		Theme {
+			background: 0xFFFFFFFF,
+			text: 0x000000FF,
+			grid: 0x00000022,
+			up: 0x0072B2FF,
+			down: 0xE69F00FF,
+			current_value: 0x009E73FF,
+			legend_background: 0xFFFFFFB0,
+			indicators: vec![0xD55E00FF, 0xCC79A7FF, 0x56B4E9FF, 0x009E73FF, 0x000000FF],
+		}
+	}
+
+	/// Colour of the indicator palette at the index, wrapping around
+	pub fn indicator(&self, index: usize) -> u32 {
+		match self.indicators.len() {
+			0 => self.text,
+			len => self.indicators[index % len],
+		}
+	}
+}
+
+#[cfg(test)]
+#[test]
+fn indicator_palette_test() {
+	let mut theme = Theme::dark();
+
+	assert_eq!(theme.indicator(0), 0xFFB000FF);
+	assert_eq!(theme.indicator(6), 0x00B4D8FF);
+
+	theme.indicators.clear();
+	assert_eq!(theme.indicator(3), theme.text);
+}
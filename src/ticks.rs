@@ -0,0 +1,172 @@
+use utils::SetAggregate;
+
+/// Side that took liquidity from the order book in a trade
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+	Buy,
+	Sell,
+}
+
+/// A single trade
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Tick {
+	pub price: f64,
+	pub size: f64,
+	/// The aggressor's side, buys add to the buy volume of the candle
+	pub side: Side,
+	/// Unix timestamp, in seconds
+	pub timestamp: i64,
+}
+
+/// When a candle built from trades is complete, `OHLCRenderOptions::bars` lays out the candles of each
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Bars {
+	/// One candle per window of the length in seconds, aligned to multiples of the length since the unix epoch.
+	/// Windows without trades have no candle.
+	Time(i64),
+	/// One candle per so many trades
+	TickCount(usize),
+	/// Candles are complete once their traded size reaches the amount, including the trade that reaches it
+	Volume(f64),
+	/// Candles are complete once their traded value (price * size) reaches the amount, including the trade that reaches it
+	DollarValue(f64),
+}
+
+/// Builds candles out of trades as they come in, trades are expected in chronological order
+#[derive(Clone, Debug)]
+pub struct CandleBuilder {
+	bars: Bars,
+	/// The candle being built
+	current: Option<SetAggregate>,
+	/// Traded value of the candle being built
+	value: f64,
+}
+
+impl CandleBuilder {
+	pub fn new(bars: Bars) -> CandleBuilder {
+		CandleBuilder { bars, current: None, value: 0. }
+	}
+
+	/// Adds the trade to the candle being built, returns the candle that's complete if there's one
+	pub fn push(&mut self, tick: Tick) -> Option<SetAggregate> {
+		let window = match self.bars {
+			Bars::Time(length) => Some(tick.timestamp - tick.timestamp.rem_euclid(length.max(1))),
+			_ => None,
+		};
+
+		// Time bars only know a candle is complete once a trade of the next window comes in
+		let mut complete = match (window, &self.current) {
+			(Some(_), Some(current)) if current.start != window => self.finish(),
+			_ => None,
+		};
+
+		self.value += tick.price * tick.size;
+
+		let candle = self.current.get_or_insert_with(|| SetAggregate {
+			o: tick.price,
+			h: tick.price,
+			l: tick.price,
+			c: tick.price,
+			bv: Some(0.),
+			start: window.or(Some(tick.timestamp)),
+			..SetAggregate::default()
+		});
+
+		candle.h = candle.h.max(tick.price);
+		candle.l = candle.l.min(tick.price);
+		candle.c = tick.price;
+		candle.v += tick.size;
+		if tick.side == Side::Buy {
+			candle.bv = candle.bv.map(|bv| bv + tick.size);
+		}
+		candle.vwap = if candle.v > 0. { self.value / candle.v } else { tick.price };
+		candle.end = Some(tick.timestamp);
+		candle.count += 1;
+
+		let full = match self.bars {
+			Bars::Time(_) => false,
+			Bars::TickCount(count) => candle.count >= count.max(1),
+			Bars::Volume(amount) => candle.v >= amount,
+			Bars::DollarValue(amount) => self.value >= amount,
+		};
+
+		if full {
+			complete = self.finish();
+		}
+
+		complete
+	}
+
+	/// Takes the candle being built, e.g. at the end of the trades. The next trade starts a new candle.
+	pub fn finish(&mut self) -> Option<SetAggregate> {
+		self.value = 0.;
+
+		self.current.take()
+	}
+}
+
+/// Builds candles out of the trades, the last candle may be incomplete
+pub fn candles_from_ticks<D: From<SetAggregate>>(ticks: &[Tick], bars: Bars) -> Vec<D> {
+	let mut builder = CandleBuilder::new(bars);
+
+	let mut candles = ticks.iter()
+		.filter_map(|&tick| builder.push(tick))
+		.map(D::from)
+		.collect::<Vec<_>>();
+
+	candles.extend(builder.finish().map(D::from));
+
+	candles
+}
+
+#[cfg(test)]
+fn trades() -> Vec<Tick> {
+	vec![
+		Tick { price: 100., size: 1., side: Side::Buy, timestamp: 0 },
+		Tick { price: 102., size: 2., side: Side::Sell, timestamp: 20 },
+		Tick { price: 99., size: 1., side: Side::Sell, timestamp: 50 },
+		Tick { price: 101., size: 4., side: Side::Buy, timestamp: 70 },
+		Tick { price: 103., size: 2., side: Side::Buy, timestamp: 190 },
+	]
+}
+
+#[cfg(test)]
+#[test]
+fn time_bars_test() {
+	use data::Candle;
+
+	let candles: Vec<SetAggregate> = candles_from_ticks(&trades(), Bars::Time(60));
+
+	assert_eq!(candles.len(), 3);
+	assert_eq!((candles[0].o, candles[0].h, candles[0].l, candles[0].c), (100., 102., 99., 99.));
+	assert_eq!((candles[0].bv, candles[0].sell_volume(), candles[0].v), (Some(1.), Some(3.), 4.));
+	assert!((candles[0].vwap - (100. + 204. + 99.) / 4.).abs() < 1e-9);
+	// Windows start at multiples of the length, the minute without trades has no candle
+	assert_eq!(candles.iter().map(|candle| candle.start).collect::<Vec<_>>(), vec![Some(0), Some(60), Some(180)]);
+	assert_eq!((candles[1].end, candles[1].count), (Some(70), 1));
+}
+
+#[cfg(test)]
+#[test]
+fn activity_bars_test() {
+	let by_count: Vec<SetAggregate> = candles_from_ticks(&trades(), Bars::TickCount(2));
+	assert_eq!(by_count.iter().map(|candle| candle.count).collect::<Vec<_>>(), vec![2, 2, 1]);
+	assert_eq!(by_count.iter().map(|candle| candle.start).collect::<Vec<_>>(), vec![Some(0), Some(50), Some(190)]);
+
+	let by_volume: Vec<SetAggregate> = candles_from_ticks(&trades(), Bars::Volume(3.));
+	assert_eq!(by_volume.iter().map(|candle| candle.v).collect::<Vec<_>>(), vec![3., 5., 2.]);
+
+	let by_value: Vec<SetAggregate> = candles_from_ticks(&trades(), Bars::DollarValue(400.));
+	assert_eq!(by_value.iter().map(|candle| candle.count).collect::<Vec<_>>(), vec![3, 1, 1]);
+}
+
+#[cfg(test)]
+#[test]
+fn streaming_test() {
+	let mut builder = CandleBuilder::new(Bars::Time(60));
+	let completed = trades().into_iter().filter_map(|tick| builder.push(tick)).count();
+
+	assert_eq!(completed, 2);
+	assert_eq!(builder.finish().map(|candle| candle.c), Some(103.));
+	assert_eq!(builder.finish(), None);
+}
@@ -1,16 +1,29 @@
+use chrono::{DateTime, Datelike, NaiveDate};
+
 use super::*;
 
 #[cfg(test)]
 mod tests;
 
-#[derive(Default)]
+/// A set of candles summed up as one candle
+#[derive(Default, Clone, Debug, PartialEq)]
 pub struct SetAggregate {
 	pub o: f64,
 	pub h: f64,
 	pub l: f64,
 	pub c: f64,
+	/// Total buy volume, only if every candle has a buy volume
 	pub bv: Option<f64>,
+	/// Total volume
 	pub v: f64,
+	/// Volume weighted average of the typical prices ((high + low + close) / 3), a plain average without any volume
+	pub vwap: f64,
+	/// Timestamp of the first candle
+	pub start: Option<i64>,
+	/// Timestamp of the last candle
+	pub end: Option<i64>,
+	/// Amount of candles in the set
+	pub count: usize,
 }
 
 impl Candle for SetAggregate {
@@ -26,12 +39,19 @@ impl Candle for SetAggregate {
 	fn buy_volume(&self) -> Option<f64> { self.bv }
 	#[inline]
 	fn total_volume(&self) -> f64 { self.v }
+	#[inline]
+	fn timestamp(&self) -> Option<i64> { self.start }
+
+	fn with_prices(&self, o: f64, h: f64, l: f64, c: f64) -> Option<SetAggregate> {
+		Some(SetAggregate { o, h, l, c, ..self.clone() })
+	}
 }
 
+/// Sums up the candles into one candle spanning all of them, no candles sum up to zeroes
 pub fn aggregate<C: Candle>(data: &[C]) -> SetAggregate {
 	let mut aggregate = SetAggregate::default();
 
-	if data.len() == 0 {
+	if data.is_empty() {
 		return aggregate;
 	}
 
@@ -39,6 +59,13 @@ pub fn aggregate<C: Candle>(data: &[C]) -> SetAggregate {
 	aggregate.h = data[0].high();
 	aggregate.l = data[0].low();
 	aggregate.c = data[data.len() - 1].close();
+	aggregate.bv = Some(0.);
+	aggregate.start = data[0].timestamp();
+	aggregate.end = data[data.len() - 1].timestamp();
+	aggregate.count = data.len();
+
+	let mut typical_sum = 0.;
+	let mut weighted_typical_sum = 0.;
 
 	for elem in data {
 		let high = elem.high();
@@ -51,10 +78,20 @@ pub fn aggregate<C: Candle>(data: &[C]) -> SetAggregate {
 			aggregate.l = low;
 		}
 
-		aggregate.bv = elem.buy_volume();
-		aggregate.v = elem.total_volume();
+		aggregate.bv = aggregate.bv.and_then(|bv| elem.buy_volume().map(|buy| bv + buy));
+		aggregate.v += elem.total_volume();
+
+		let typical = (high + low + elem.close()) / 3.;
+		typical_sum += typical;
+		weighted_typical_sum += typical * elem.total_volume();
 	}
 
+	aggregate.vwap = if aggregate.v > 0. {
+		weighted_typical_sum / aggregate.v
+	} else {
+		typical_sum / data.len() as f64
+	};
+
 	aggregate
 }
 
@@ -65,6 +102,15 @@ const LEN_OF_WEEK: u64 = 7 * LEN_OF_DAY;
 const LEN_OF_MONTH: u64 = 30 * LEN_OF_DAY;
 const LEN_OF_YEAR: u64 = 365 * LEN_OF_DAY;
 
+/// Human-friendly time intervals, in seconds, in ascending order
+const NICE_TIME_INTERVALS: [u64; 22] = [
+	LEN_OF_MINUTE, 2 * LEN_OF_MINUTE, 5 * LEN_OF_MINUTE, 10 * LEN_OF_MINUTE, 15 * LEN_OF_MINUTE, 30 * LEN_OF_MINUTE,
+	LEN_OF_HOUR, 2 * LEN_OF_HOUR, 3 * LEN_OF_HOUR, 4 * LEN_OF_HOUR, 6 * LEN_OF_HOUR, 12 * LEN_OF_HOUR,
+	LEN_OF_DAY, 2 * LEN_OF_DAY, LEN_OF_WEEK, 2 * LEN_OF_WEEK,
+	LEN_OF_MONTH, 2 * LEN_OF_MONTH, 3 * LEN_OF_MONTH, 6 * LEN_OF_MONTH,
+	LEN_OF_YEAR, 2 * LEN_OF_YEAR,
+];
+
 pub fn duration_string(elapsed: u64) -> String {
 	if elapsed < 10 {
 		return "Now".to_string();
@@ -106,3 +152,89 @@ pub fn duration_string(elapsed: u64) -> String {
 
 	elapsed_str
 }
+
+/// Unix timestamps between `from` and `to` (inclusive) that fall on natural boundaries of the interval in local time.
+///
+/// Intervals of 28 days or more are rounded to whole months starting from January, whole weeks start on Monday and
+/// everything else is aligned to multiples of the interval since local midnight of the epoch.
+pub fn aligned_times(from: i64, to: i64, interval: i64, utc_offset: i32) -> Vec<i64> {
+	let mut times = vec![];
+
+	if interval <= 0 {
+		return times;
+	}
+
+	let offset = utc_offset as i64;
+
+	if interval >= 28 * LEN_OF_DAY as i64 {
+		let months = ((interval as f64 / LEN_OF_MONTH as f64).round() as i64).max(1);
+		let local = match DateTime::from_timestamp(from + offset, 0) {
+			Some(dt) => dt.naive_utc(),
+			None => return times,
+		};
+
+		let mut index = local.year() as i64 * 12 + local.month0() as i64;
+		index -= index.rem_euclid(months);
+
+		loop {
+			let date = NaiveDate::from_ymd_opt(index.div_euclid(12) as i32, index.rem_euclid(12) as u32 + 1, 1);
+			let time = match date.and_then(|date| date.and_hms_opt(0, 0, 0)) {
+				Some(midnight) => midnight.and_utc().timestamp() - offset,
+				None => break,
+			};
+
+			if time > to {
+				break;
+			}
+			if time >= from {
+				times.push(time);
+			}
+
+			index += months;
+		}
+	} else {
+		// 1969-12-29 was a Monday
+		let anchor = if interval % LEN_OF_WEEK as i64 == 0 { -3 * LEN_OF_DAY as i64 } else { 0 };
+		let local_from = from + offset - anchor;
+
+		let mut time = local_from + (interval - local_from.rem_euclid(interval)) % interval - offset + anchor;
+		while time <= to {
+			times.push(time);
+			time += interval;
+		}
+	}
+
+	times
+}
+
+/// The smallest interval of the 1, 2, 5 x 10^n sequence that splits the range into at most `count` parts
+pub fn nice_price_interval(range: f64, count: usize) -> f64 {
+	let raw = range / count.max(1) as f64;
+
+	if raw <= 0. || !raw.is_finite() {
+		return 1.;
+	}
+
+	let magnitude = 10_f64.powf(raw.log10().floor());
+	let multiplier = [1., 2., 5., 10.].iter()
+		.cloned()
+		.find(|m| m * magnitude >= raw * (1. - 1e-9))
+		.unwrap_or(10.);
+
+	multiplier * magnitude
+}
+
+/// The smallest of the minute, hour, day, week, month or year based intervals that splits the timeframe into at most `count` parts
+pub fn nice_time_interval(timeframe: i64, count: usize) -> i64 {
+	let raw = timeframe / count.max(1) as i64;
+
+	for interval in NICE_TIME_INTERVALS.iter() {
+		if *interval as i64 >= raw {
+			return *interval as i64;
+		}
+	}
+
+	// Whole years beyond that
+	let years = (raw + LEN_OF_YEAR as i64 - 1) / LEN_OF_YEAR as i64;
+	years * LEN_OF_YEAR as i64
+}
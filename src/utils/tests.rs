@@ -17,3 +17,102 @@ fn duration_test() {
 	assert_eq!(duration_string(365 * 86400), "1y");
 	assert_eq!(duration_string(365 * 86400 + 2592000 + 604800 + 86400 + 3661), "1y1m1w1d1h1m1s");
 }
+
+#[test]
+fn aligned_times_hours_test() {
+	// 2018-09-27 00:30:00 UTC
+	let from = 1538008200;
+
+	assert_eq!(aligned_times(from, from + 6 * 3600, 6 * 3600, 0), vec![from + 5 * 3600 + 1800]);
+	assert_eq!(aligned_times(from, from + 3600, 3600, 0), vec![from + 1800]);
+	// Local midnight in UTC+10 is 14:00 UTC the day before
+	assert_eq!(aligned_times(from, from + 86400, 86400, 36000), vec![from + 13 * 3600 + 1800]);
+	assert!(aligned_times(from, from + 86400, 0, 0).is_empty());
+}
+
+#[test]
+fn aligned_times_weeks_test() {
+	// 2018-09-27 00:00:00 UTC, a Thursday
+	let from = 1538006400;
+
+	// Next Monday, 2018-10-01
+	assert_eq!(aligned_times(from, from + 14 * 86400, 7 * 86400, 0), vec![from + 4 * 86400, from + 11 * 86400]);
+}
+
+#[test]
+fn aligned_times_months_test() {
+	// 2018-09-27 00:00:00 UTC
+	let from = 1538006400;
+
+	// 2018-10-01, 2018-11-01 and 2018-12-01
+	assert_eq!(aligned_times(from, from + 70 * 86400, 30 * 86400, 0), vec![1538352000, 1541030400, 1543622400]);
+	// Quarters start in January, April, July and October
+	assert_eq!(aligned_times(from, from + 70 * 86400, 90 * 86400, 0), vec![1538352000]);
+}
+
+#[test]
+fn nice_price_interval_test() {
+	assert_eq!(nice_price_interval(2400., 10), 500.);
+	assert_eq!(nice_price_interval(2000., 10), 200.);
+	assert_eq!(nice_price_interval(1000., 10), 100.);
+	assert_eq!(nice_price_interval(0.0012, 10), 0.0002);
+	assert_eq!(nice_price_interval(0., 10), 1.);
+	assert_eq!(nice_price_interval(100., 0), 100.);
+}
+
+#[test]
+fn nice_time_interval_test() {
+	assert_eq!(nice_time_interval(168 * 3600, 8), 86400);
+	assert_eq!(nice_time_interval(60 * 60, 10), 10 * 60);
+	assert_eq!(nice_time_interval(90 * 86400, 8), 14 * 86400);
+	assert_eq!(nice_time_interval(20 * 365 * 86400, 4), 5 * 365 * 86400);
+}
+
+struct VolumeCandle {
+	ohlc: (f64, f64, f64, f64),
+	buy_volume: Option<f64>,
+	volume: f64,
+	timestamp: i64,
+}
+
+impl Candle for VolumeCandle {
+	fn open(&self) -> f64 { self.ohlc.0 }
+	fn high(&self) -> f64 { self.ohlc.1 }
+	fn low(&self) -> f64 { self.ohlc.2 }
+	fn close(&self) -> f64 { self.ohlc.3 }
+	fn buy_volume(&self) -> Option<f64> { self.buy_volume }
+	fn total_volume(&self) -> f64 { self.volume }
+	fn timestamp(&self) -> Option<i64> { Some(self.timestamp) }
+}
+
+#[test]
+fn aggregate_test() {
+	let mut data = vec![
+		VolumeCandle { ohlc: (10., 12., 9., 11.), buy_volume: Some(3.), volume: 5., timestamp: 60 },
+		VolumeCandle { ohlc: (11., 15., 10., 14.), buy_volume: Some(1.), volume: 10., timestamp: 120 },
+		VolumeCandle { ohlc: (14., 14., 6., 9.), buy_volume: Some(4.), volume: 5., timestamp: 180 },
+	];
+
+	let set = aggregate(&data);
+
+	assert_eq!((set.o, set.h, set.l, set.c), (10., 15., 6., 9.));
+	assert_eq!((set.bv, set.v, set.sell_volume()), (Some(8.), 20., Some(12.)));
+	// Typical prices of 32/3, 13 and 29/3, weighted 5, 10 and 5
+	assert!((set.vwap - (5. * 32. / 3. + 10. * 13. + 5. * 29. / 3.) / 20.).abs() < 1e-9);
+	assert_eq!((set.start, set.end, set.count), (Some(60), Some(180), 3));
+
+	// Buy volume totals need the buy volume of every candle
+	data[1].buy_volume = None;
+	assert_eq!(aggregate(&data).bv, None);
+
+	// Plain average without any volume
+	for candle in data.iter_mut() {
+		candle.volume = 0.;
+	}
+	assert!((aggregate(&data).vwap - (32. / 3. + 13. + 29. / 3.) / 3.).abs() < 1e-9);
+}
+
+#[test]
+fn aggregate_empty_test() {
+	assert_eq!(aggregate::<VolumeCandle>(&[]), SetAggregate::default());
+}
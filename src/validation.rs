@@ -0,0 +1,229 @@
+use std::fmt;
+
+use data::Candle;
+use error::{CandleField, OhlcError};
+
+/// What's wrong with a value of a candle
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+	/// NaN or infinite
+	NotFinite,
+	/// Below zero
+	Negative,
+	/// Zero or below, which logarithmic price scales can't show
+	NotPositive,
+	/// Above the high of the candle
+	AboveHigh,
+	/// Below the low of the candle
+	BelowLow,
+	/// The buy volume is above the total volume
+	AboveTotalVolume,
+}
+
+impl ViolationKind {
+	pub fn description(&self) -> &'static str {
+		match *self {
+			ViolationKind::NotFinite => "The value is NaN or infinite.",
+			ViolationKind::Negative => "The value is negative.",
+			ViolationKind::NotPositive => "Logarithmic price scales need positive prices.",
+			ViolationKind::AboveHigh => "The value is higher than the high value.",
+			ViolationKind::BelowLow => "The value is lower than the low value.",
+			ViolationKind::AboveTotalVolume => "The buy volume is higher than the total volume.",
+		}
+	}
+}
+
+/// A value of a candle that doesn't make sense
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Violation {
+	/// Position of the candle in the data
+	pub index: usize,
+	pub field: CandleField,
+	pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		OhlcError::from(*self).fmt(f)
+	}
+}
+
+impl From<Violation> for OhlcError {
+	fn from(violation: Violation) -> OhlcError {
+		OhlcError::Validation { index: violation.index, field: violation.field, kind: violation.kind }
+	}
+}
+
+/// What rendering does with candles that don't make sense
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValidationPolicy {
+	/// Fail with the first violation
+	#[default]
+	Reject,
+	/// Leave out the candles with violations
+	Skip,
+	/// Swap highs and lows that are the wrong way around and clamp opens and closes into them,
+	/// candles with other violations or that can't be rebuilt (see `Candle::with_prices`) are left out
+	Repair,
+}
+
+impl ValidationPolicy {
+	/// The candles to render according to the policy
+	pub fn apply<C: Candle>(&self, data: Vec<C>) -> Result<Vec<C>, OhlcError> {
+		let violations = validate(&data);
+
+		if violations.is_empty() {
+			return Ok(data);
+		}
+
+		if *self == ValidationPolicy::Reject {
+			return Err(violations[0].into());
+		}
+
+		let mut violations = violations.into_iter().peekable();
+		let mut kept = Vec::with_capacity(data.len());
+
+		for (index, candle) in data.into_iter().enumerate() {
+			let mut repairable = true;
+			let mut valid = true;
+
+			while let Some(violation) = violations.peek().filter(|violation| violation.index == index).cloned() {
+				valid = false;
+				repairable &= violation.kind == ViolationKind::AboveHigh || violation.kind == ViolationKind::BelowLow;
+				violations.next();
+			}
+
+			if valid {
+				kept.push(candle);
+			} else if *self == ValidationPolicy::Repair && repairable {
+				match repair(&candle) {
+					Some(repaired) => kept.push(repaired),
+					None => warn!("Left out candle {} as it can't be repaired", index),
+				}
+			} else {
+				warn!("Left out invalid candle {}", index);
+			}
+		}
+
+		Ok(kept)
+	}
+}
+
+/// The candle with its high and low the right way around and its open and close between them
+fn repair<C: Candle>(candle: &C) -> Option<C> {
+	let high = candle.high().max(candle.low());
+	let low = candle.high().min(candle.low());
+
+	candle.with_prices(candle.open().clamp(low, high), high, low, candle.close().clamp(low, high))
+}
+
+/// Finds every value that doesn't make sense, in the order of the candles
+pub fn validate<C: Candle>(data: &[C]) -> Vec<Violation> {
+	let mut violations = vec![];
+
+	for (index, candle) in data.iter().enumerate() {
+		let mut violation = |field, kind| violations.push(Violation { index, field, kind });
+
+		let prices = [
+			(CandleField::Open, candle.open()),
+			(CandleField::High, candle.high()),
+			(CandleField::Low, candle.low()),
+			(CandleField::Close, candle.close()),
+		];
+
+		for &(field, price) in &prices {
+			if !price.is_finite() {
+				violation(field, ViolationKind::NotFinite);
+			} else if price < 0. {
+				violation(field, ViolationKind::Negative);
+			}
+		}
+
+		let (high, low) = (candle.high(), candle.low());
+
+		if high.is_finite() && low.is_finite() {
+			for &(field, price) in &[(CandleField::Open, candle.open()), (CandleField::Close, candle.close())] {
+				if price > high {
+					violation(field, ViolationKind::AboveHigh);
+				} else if price < low {
+					violation(field, ViolationKind::BelowLow);
+				}
+			}
+
+			if low > high {
+				violation(CandleField::Low, ViolationKind::AboveHigh);
+			}
+		}
+
+		let volume = candle.total_volume();
+
+		if !volume.is_finite() {
+			violation(CandleField::Volume, ViolationKind::NotFinite);
+		} else if volume < 0. {
+			violation(CandleField::Volume, ViolationKind::Negative);
+		}
+
+		if let Some(buy_volume) = candle.buy_volume() {
+			if !buy_volume.is_finite() {
+				violation(CandleField::BuyVolume, ViolationKind::NotFinite);
+			} else if buy_volume < 0. {
+				violation(CandleField::BuyVolume, ViolationKind::Negative);
+			} else if buy_volume > volume {
+				violation(CandleField::BuyVolume, ViolationKind::AboveTotalVolume);
+			}
+		}
+	}
+
+	violations
+}
+
+#[cfg(test)]
+#[test]
+fn validate_test() {
+	use model::data::OHLC;
+
+	let data = vec![
+		OHLC { o: 1., h: 2., l: 0.5, c: 1.5, t: None },
+		OHLC { o: 3., h: 2., l: 0.5, c: 0.2, t: None },
+		OHLC { o: f64::NAN, h: 2., l: -1., c: 1., t: None },
+		OHLC { o: 1., h: 1., l: 2., c: 1.5, t: None },
+	];
+
+	let found = validate(&data).into_iter().map(|violation| (violation.index, violation.field, violation.kind)).collect::<Vec<_>>();
+
+	assert_eq!(found, vec![
+		(1, CandleField::Open, ViolationKind::AboveHigh),
+		(1, CandleField::Close, ViolationKind::BelowLow),
+		(2, CandleField::Open, ViolationKind::NotFinite),
+		(2, CandleField::Low, ViolationKind::Negative),
+		(3, CandleField::Open, ViolationKind::BelowLow),
+		(3, CandleField::Close, ViolationKind::AboveHigh),
+		(3, CandleField::Low, ViolationKind::AboveHigh),
+	]);
+}
+
+#[cfg(test)]
+#[test]
+fn policy_test() {
+	use model::data::OHLC;
+
+	let data = vec![
+		OHLC { o: 1., h: 2., l: 0.5, c: 1.5, t: None },
+		OHLC { o: 3., h: 2., l: 0.5, c: 0.2, t: None },
+		OHLC { o: f64::NAN, h: 2., l: 1., c: 1., t: None },
+		OHLC { o: 1.5, h: 1., l: 2., c: 1.5, t: None },
+	];
+
+	match ValidationPolicy::Reject.apply(data.clone()) {
+		Err(OhlcError::Validation { index: 1, field: CandleField::Open, kind: ViolationKind::AboveHigh }) => {}
+		result => panic!("expected the first violation, got {:?}", result),
+	}
+
+	assert_eq!(ValidationPolicy::Skip.apply(data.clone()).unwrap(), vec![data[0]]);
+
+	assert_eq!(ValidationPolicy::Repair.apply(data.clone()).unwrap(), vec![
+		data[0],
+		OHLC { o: 2., h: 2., l: 0.5, c: 0.5, t: None },
+		OHLC { o: 1.5, h: 2., l: 1., c: 1.5, t: None },
+	]);
+}